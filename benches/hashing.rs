@@ -0,0 +1,63 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sha2::{Digest, Sha256};
+
+use blockchain::{Amount, Block, HeaderHasher, Wallet};
+
+// 拆分前每次尝试 nonce 的做法: 重新序列化全部交易再拼接字符串
+fn legacy_hash(block: &Block, nonce: u64) -> String {
+    let data = format!(
+        "{}{}{}{}{}",
+        block.index(),
+        block.timestamp(),
+        serde_json::to_string(block.transactions()).unwrap(),
+        block.previous_hash(),
+        nonce
+    );
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+fn sample_block(transactions: usize) -> Block {
+    let wallet = Wallet::generate();
+    let transactions = (0..transactions)
+        .map(|i| {
+            wallet.transfer(
+                format!("recipient{}", i),
+                Amount::from_base_units(1),
+                i as u64,
+            )
+        })
+        .collect();
+    Block::new(1, transactions, "0".repeat(64))
+}
+
+fn bench_nonce_hashing(c: &mut Criterion) {
+    let block = sample_block(100);
+    let hasher = HeaderHasher::new(&block.header());
+    let mut header = block.header();
+
+    let mut group = c.benchmark_group("hash_per_nonce");
+    group.bench_function("legacy_json", |b| {
+        let mut nonce = 0u64;
+        b.iter(|| {
+            nonce += 1;
+            black_box(legacy_hash(&block, nonce))
+        })
+    });
+    group.bench_function("binary_header", |b| {
+        b.iter(|| {
+            header.nonce += 1;
+            black_box(header.hash())
+        })
+    });
+    group.bench_function("precomputed_prefix", |b| {
+        let mut nonce = 0u64;
+        b.iter(|| {
+            nonce += 1;
+            black_box(hasher.hash_with_nonce(nonce))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_nonce_hashing);
+criterion_main!(benches);
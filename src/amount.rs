@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ChainError;
+
+// 默认精度: 1 个币 = 10^8 个最小单位
+pub const DEFAULT_DECIMALS: u32 = 8;
+
+// 金额, 以最小单位的整数存储, 避免浮点误差
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub const fn from_base_units(units: u64) -> Self {
+        Amount(units)
+    }
+
+    pub const fn base_units(self) -> u64 {
+        self.0
+    }
+
+    // 按默认精度把整数个币转换为金额
+    pub fn from_coins(coins: u64) -> Result<Self, ChainError> {
+        coins
+            .checked_mul(10u64.pow(DEFAULT_DECIMALS))
+            .map(Amount)
+            .ok_or(ChainError::AmountOverflow)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn saturating_add(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+
+    // 解析十进制字符串, 例如 "12.5", 小数位数不能超过 decimals
+    pub fn parse_with_decimals(s: &str, decimals: u32) -> Result<Self, ChainError> {
+        let invalid = || ChainError::InvalidAmount(s.to_string());
+        let scale = 10u64
+            .checked_pow(decimals)
+            .ok_or(ChainError::AmountOverflow)?;
+
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if whole.is_empty() || fraction.len() > decimals as usize {
+            return Err(invalid());
+        }
+        if !whole
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let whole: u64 = whole.parse().map_err(|_| ChainError::AmountOverflow)?;
+        let fraction: u64 = if fraction.is_empty() {
+            0
+        } else {
+            let padding = 10u64.pow(decimals - fraction.len() as u32);
+            fraction.parse::<u64>().map_err(|_| invalid())? * padding
+        };
+
+        whole
+            .checked_mul(scale)
+            .and_then(|units| units.checked_add(fraction))
+            .map(Amount)
+            .ok_or(ChainError::AmountOverflow)
+    }
+
+    // 按指定精度格式化, 去掉末尾多余的 0
+    pub fn format_with_decimals(self, decimals: u32) -> String {
+        let Some(scale) = 10u64.checked_pow(decimals) else {
+            return self.0.to_string();
+        };
+        let whole = self.0 / scale;
+        let fraction = self.0 % scale;
+        if fraction == 0 {
+            return whole.to_string();
+        }
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        format!("{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format_with_decimals(DEFAULT_DECIMALS))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = ChainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Amount::parse_with_decimals(s, DEFAULT_DECIMALS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_round_trip() {
+        let amount: Amount = "12.5".parse().unwrap();
+        assert_eq!(amount.base_units(), 1_250_000_000);
+        assert_eq!(amount.to_string(), "12.5");
+        assert_eq!(Amount::from_coins(100).unwrap().to_string(), "100");
+        assert_eq!(Amount::from_base_units(1).to_string(), "0.00000001");
+        assert_eq!(
+            Amount::from_base_units(1234).format_with_decimals(2),
+            "12.34"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_input() {
+        for input in ["-5", "", ".5", "1.2.3", "abc", "1e3", "0.000000001"] {
+            assert!(matches!(
+                input.parse::<Amount>(),
+                Err(ChainError::InvalidAmount(_))
+            ));
+        }
+        assert!(matches!(
+            "184467440737.09551616".parse::<Amount>(),
+            Err(ChainError::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let max = Amount::from_base_units(u64::MAX);
+        assert_eq!(max.checked_add(Amount::from_base_units(1)), None);
+        assert_eq!(Amount::ZERO.checked_sub(Amount::from_base_units(1)), None);
+    }
+}
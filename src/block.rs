@@ -0,0 +1,172 @@
+use chrono::Utc;
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::header::{BlockHeader, HEADER_VERSION};
+use crate::merkle::{self, MerkleProof};
+use crate::target::Target;
+use crate::tx::Transaction;
+
+// 区块结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Block {
+    pub(crate) version: u32,
+    pub(crate) index: u64,
+    pub(crate) timestamp: i64,
+    pub(crate) transactions: Vec<Transaction>,
+    pub(crate) previous_hash: String,
+    pub(crate) merkle_root: String,
+    // 本区块需要满足的目标值 (紧凑表示)
+    pub(crate) bits: u32,
+    pub(crate) hash: String,
+    pub(crate) nonce: u64,
+    // 权威证明等共识下出块者对区块哈希的签名
+    #[serde(default)]
+    pub(crate) signature: Option<String>,
+}
+
+impl Block {
+    pub fn new(index: u64, transactions: Vec<Transaction>, previous_hash: String) -> Self {
+        let mut block = Block {
+            version: HEADER_VERSION,
+            index,
+            timestamp: Utc::now().timestamp(),
+            transactions,
+            previous_hash,
+            merkle_root: String::new(),
+            bits: 0,
+            hash: String::new(),
+            nonce: 0,
+            signature: None,
+        };
+        block.merkle_root = block.calculate_merkle_root();
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    // 创世区块, 记录链的初始目标值
+    pub fn genesis(bits: u32) -> Self {
+        let mut block = Block::new(0, vec![], "0".repeat(64));
+        block.bits = bits;
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    // 区块哈希只覆盖区块头, 交易通过默克尔根间接提交
+    pub fn calculate_hash(&self) -> String {
+        hex::encode(self.calculate_hash_bytes())
+    }
+
+    pub fn calculate_hash_bytes(&self) -> [u8; 32] {
+        self.header().hash()
+    }
+
+    // 二进制区块头, 无法解码的十六进制哈希按全零处理 (校验时会单独比较字符串)
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            version: self.version,
+            index: self.index,
+            previous_hash: merkle::decode_hash(&self.previous_hash).unwrap_or_default(),
+            merkle_root: merkle::decode_hash(&self.merkle_root).unwrap_or_default(),
+            timestamp: self.timestamp,
+            bits: self.bits,
+            nonce: self.nonce,
+        }
+    }
+
+    pub fn calculate_merkle_root(&self) -> String {
+        hex::encode(merkle::merkle_root(&self.transaction_hashes()))
+    }
+
+    // 为区块中哈希为 tx_hash 的交易生成包含证明
+    pub fn merkle_proof(&self, tx_hash: &str) -> Option<MerkleProof> {
+        let leaves = self.transaction_hashes();
+        let position = leaves
+            .iter()
+            .position(|leaf| hex::encode(leaf) == tx_hash)?;
+        MerkleProof::generate(&leaves, position)
+    }
+
+    fn transaction_hashes(&self) -> Vec<[u8; 32]> {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.hash_bytes())
+            .collect()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    pub fn previous_hash(&self) -> &str {
+        &self.previous_hash
+    }
+
+    pub fn merkle_root(&self) -> &str {
+        &self.merkle_root
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn target(&self) -> Option<Target> {
+        Target::from_compact(self.bits)
+    }
+
+    // 本区块贡献的工作量, 目标值无效时为 0
+    pub fn work(&self) -> U256 {
+        self.target()
+            .map(|target| target.work())
+            .unwrap_or_default()
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn signature(&self) -> Option<&str> {
+        self.signature.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+
+    #[test]
+    fn test_merkle_proof_for_block_transaction() {
+        let transactions: Vec<Transaction> = (1..=5)
+            .map(|n| Transaction::coinbase(format!("miner{}", n), Amount::from_coins(n).unwrap()))
+            .collect();
+        let block = Block::new(1, transactions.clone(), "0".repeat(64));
+
+        for transaction in &transactions {
+            let proof = block.merkle_proof(&transaction.hash()).unwrap();
+            assert!(proof.verify(block.merkle_root()));
+        }
+        assert!(block.merkle_proof(&"0".repeat(64)).is_none());
+
+        // 证明不能用于其他区块
+        let other = Block::new(1, transactions[..2].to_vec(), "0".repeat(64));
+        let proof = block.merkle_proof(&transactions[0].hash()).unwrap();
+        assert!(!proof.verify(other.merkle_root()));
+    }
+}
@@ -0,0 +1,1972 @@
+use chrono::Utc;
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::amount::Amount;
+use crate::block::Block;
+use crate::config::{ChainConfig, LedgerMode};
+use crate::consensus::{ConsensusEngine, ProofOfWork, SealOutcome};
+use crate::error::ChainError;
+use crate::events::ChainEvent;
+use crate::mempool::Mempool;
+use crate::mining::{CancellationToken, MiningOutcome, MiningProgress};
+use crate::state::ChainState;
+use crate::storage::BlockStore;
+use crate::tree::BlockTree;
+use crate::tx::{Transaction, TxKind};
+use crate::utxo::{OutPoint, TxOutput};
+
+// 外部区块提交后的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockStatus {
+    // 延长了主链
+    Connected,
+    // 保存在工作量不占优的侧链上
+    SideChain,
+    // 所在分支超过主链, 链重组断开了 depth 个区块
+    Reorganized { depth: u64 },
+    // 父区块未知, 暂存在孤块池中
+    Orphan,
+}
+
+// 区块链的可反序列化快照: 主链和参数
+// 序列化 Blockchain 得到的 JSON 也可以读成快照, 恢复时需要另外提供共识引擎
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSnapshot {
+    pub chain: Vec<Block>,
+    pub config: ChainConfig,
+}
+
+// 区块链结构
+#[derive(Debug, Serialize)]
+pub struct Blockchain {
+    // 当前主链, 所有已知分支保存在 tree 中
+    chain: Vec<Block>,
+    #[serde(skip)]
+    tree: BlockTree,
+    // 主链链尾处的账户状态
+    #[serde(skip)]
+    state: ChainState,
+    // 等待打包的交易
+    mempool: Mempool,
+    config: ChainConfig,
+    #[serde(skip)]
+    consensus: Box<dyn ConsensusEngine>,
+    #[serde(skip)]
+    store: Option<Box<dyn BlockStore>>,
+    #[serde(skip)]
+    subscribers: Vec<Sender<ChainEvent>>,
+}
+
+impl Blockchain {
+    // bits 为初始目标值的紧凑表示, 参见 Target::to_compact
+    pub fn new(bits: u32, mining_reward: Amount) -> Self {
+        Self::with_config(ChainConfig::new(bits, mining_reward))
+    }
+
+    // 使用工作量证明共识
+    pub fn with_config(config: ChainConfig) -> Self {
+        let consensus = Box::new(ProofOfWork::from_config(&config));
+        Self::with_consensus(config, consensus)
+            .expect("proof-of-work genesis block has no transactions")
+    }
+
+    // 共识引擎的创世区块不能在所选账本模式下应用时返回错误,
+    // 例如权益证明的创世质押交易在 UTXO 模式下没有输入
+    pub fn with_consensus(
+        config: ChainConfig,
+        consensus: Box<dyn ConsensusEngine>,
+    ) -> Result<Self, ChainError> {
+        // 创建创世区块
+        let genesis_block = consensus.genesis(&config);
+        let tree = BlockTree::new(genesis_block.clone(), consensus.block_work(&genesis_block));
+        let state = ChainState::from_blocks(config.ledger, std::slice::from_ref(&genesis_block))?;
+
+        Ok(Blockchain {
+            chain: vec![genesis_block],
+            tree,
+            state,
+            mempool: Mempool::new(config.mempool.clone()),
+            config,
+            consensus,
+            store: None,
+            subscribers: Vec::new(),
+        })
+    }
+
+    // 从存储中恢复区块链, 存储为空时写入新的创世区块
+    pub fn open(store: Box<dyn BlockStore>, config: ChainConfig) -> Result<Self, ChainError> {
+        let consensus = Box::new(ProofOfWork::from_config(&config));
+        Self::open_with_consensus(store, config, consensus)
+    }
+
+    pub fn open_with_consensus(
+        mut store: Box<dyn BlockStore>,
+        config: ChainConfig,
+        consensus: Box<dyn ConsensusEngine>,
+    ) -> Result<Self, ChainError> {
+        let mut chain = store.load_blocks()?;
+        if chain.is_empty() {
+            let genesis_block = consensus.genesis(&config);
+            store.append(&genesis_block)?;
+            chain.push(genesis_block);
+        }
+        Self::from_chain(chain, config, consensus, Some(store))
+    }
+
+    // 当前主链和参数的快照
+    pub fn snapshot(&self) -> ChainSnapshot {
+        ChainSnapshot {
+            chain: self.chain.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    // 从快照恢复区块链, 整条链按 consensus 重新校验, 交易池为空
+    pub fn from_snapshot(
+        snapshot: ChainSnapshot,
+        consensus: Box<dyn ConsensusEngine>,
+    ) -> Result<Self, ChainError> {
+        if snapshot.chain.is_empty() {
+            return Err(ChainError::EmptyChain);
+        }
+        Self::from_chain(snapshot.chain, snapshot.config, consensus, None)
+    }
+
+    // 校验从创世区块开始的完整主链并重建状态和区块树
+    fn from_chain(
+        chain: Vec<Block>,
+        config: ChainConfig,
+        consensus: Box<dyn ConsensusEngine>,
+        store: Option<Box<dyn BlockStore>>,
+    ) -> Result<Self, ChainError> {
+        let tree = BlockTree::new(chain[0].clone(), consensus.block_work(&chain[0]));
+        let state = ChainState::from_blocks(config.ledger, &chain)?;
+        let mut blockchain = Blockchain {
+            chain,
+            tree,
+            state,
+            mempool: Mempool::new(config.mempool.clone()),
+            config,
+            consensus,
+            store,
+            subscribers: Vec::new(),
+        };
+        blockchain.is_chain_valid()?;
+        for block in &blockchain.chain[1..] {
+            let work = blockchain.consensus.block_work(block);
+            blockchain.tree.insert(block.clone(), work)?;
+        }
+        Ok(blockchain)
+    }
+
+    pub fn chain(&self) -> &[Block] {
+        &self.chain
+    }
+
+    // 包含所有已知分支和孤块的区块树
+    pub fn block_tree(&self) -> &BlockTree {
+        &self.tree
+    }
+
+    // 订阅区块链事件, 接收端被丢弃后自动取消订阅
+    pub fn subscribe(&mut self) -> Receiver<ChainEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    fn emit(&mut self, event: ChainEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    // 主链链尾处的账户状态缓存
+    pub fn state(&self) -> &ChainState {
+        &self.state
+    }
+
+    pub fn mempool(&self) -> &Mempool {
+        &self.mempool
+    }
+
+    // 按到达顺序排列的待打包交易
+    pub fn pending_transactions(&self) -> Vec<&Transaction> {
+        self.mempool.transactions()
+    }
+
+    pub fn config(&self) -> &ChainConfig {
+        &self.config
+    }
+
+    pub fn consensus(&self) -> &dyn ConsensusEngine {
+        self.consensus.as_ref()
+    }
+
+    // 链上所有区块的累计工作量
+    pub fn chain_work(&self) -> U256 {
+        self.chain.iter().fold(U256::zero(), |total, block| {
+            total.saturating_add(self.consensus.block_work(block))
+        })
+    }
+
+    pub fn mining_reward(&self) -> Amount {
+        self.config.mining_reward
+    }
+
+    pub fn get_latest_block(&self) -> Result<&Block, ChainError> {
+        self.chain.last().ok_or(ChainError::EmptyChain)
+    }
+
+    // 挖出一个打包待处理交易的区块并加入链中, 手续费率高的交易优先
+    pub fn mine_pending_transactions(
+        &mut self,
+        miner_address: String,
+    ) -> Result<&Block, ChainError> {
+        // 令牌从未被取消, 结果一定是 Found 且区块已加入链中
+        self.mine_pending_transactions_with(miner_address, &CancellationToken::new(), None)?;
+        self.get_latest_block()
+    }
+
+    // 可取消的挖矿: 找到解时把区块加入链中, 被取消时链和交易池都保持不变
+    pub fn mine_pending_transactions_with(
+        &mut self,
+        miner_address: String,
+        cancel: &CancellationToken,
+        progress: Option<&mut dyn FnMut(MiningProgress)>,
+    ) -> Result<MiningOutcome, ChainError> {
+        let mut block = self.create_block_template(miner_address)?;
+        match self
+            .consensus
+            .seal(&mut block, &self.chain, &self.state, cancel, progress)?
+        {
+            SealOutcome::Sealed(stats) => {
+                self.add_block(block.clone())?;
+                Ok(MiningOutcome::Found { block, stats })
+            }
+            SealOutcome::Cancelled(stats) => Ok(MiningOutcome::Cancelled { stats }),
+        }
+    }
+
+    // 构造待封装的新区块: 打包待处理交易和挖矿奖励, 填好共识字段
+    // 可以交给其他线程封装, 完成后通过 add_block 提交
+    pub fn create_block_template(&self, miner_address: String) -> Result<Block, ChainError> {
+        let latest_block = self.get_latest_block()?;
+
+        // 创建挖矿奖励交易, 矿工获得区块奖励和所有打包交易的手续费
+        let mut transactions = self.mempool.select(
+            |sender| self.state.nonce(sender),
+            self.config.max_block_transactions,
+            self.config.max_block_size,
+        );
+
+        // 在状态副本上逐笔试连接, 跳过无法应用的交易, 之后同一 sender 的交易因序号不连续一并跳过
+        let mut trial = self.state.clone();
+        transactions.retain(|transaction| {
+            let block = Block::new(
+                latest_block.index + 1,
+                vec![transaction.clone()],
+                latest_block.hash.clone(),
+            );
+            trial.connect_block(&block).is_ok()
+        });
+        let reward =
+            transactions
+                .iter()
+                .try_fold(self.config.mining_reward, |total, transaction| {
+                    total
+                        .checked_add(transaction.fee())
+                        .ok_or(ChainError::AmountOverflow)
+                })?;
+        let mut reward_tx = Transaction::coinbase(miner_address, reward);
+        // 高位写入区块高度, 保证不同区块的挖矿奖励交易哈希不同, UTXO 模式下输出不会重复
+        reward_tx.set_extra_nonce((latest_block.index + 1) << 32);
+        transactions.push(reward_tx);
+
+        let mut block = Block::new(
+            latest_block.index + 1,
+            transactions,
+            latest_block.hash.clone(),
+        );
+        self.consensus.prepare(&mut block, &self.chain)?;
+        Ok(block)
+    }
+
+    // 校验区块后追加到链尾
+    // 父区块是其他分支上的已知区块时作为侧链区块保存
+    pub fn add_block(&mut self, block: Block) -> Result<(), ChainError> {
+        self.accept_block(block).map(|_| ())
+    }
+
+    // 接收其他节点产生的区块: 完整校验后连接到区块树,
+    // 父区块未知时先做不依赖上下文的校验再放入孤块池, 父区块到达后自动连接
+    pub fn submit_block(&mut self, block: Block) -> Result<BlockStatus, ChainError> {
+        if self.tree.is_orphan(&block.hash) {
+            return Err(ChainError::DuplicateBlock { hash: block.hash });
+        }
+        if !self.tree.contains(&block.previous_hash) {
+            self.check_block(&block)?;
+            self.consensus.check_header(&block)?;
+
+            // 先移除过期的孤块, 孤块池已满时淘汰最早到达的孤块
+            let now = Utc::now().timestamp();
+            self.tree
+                .expire_orphans(now.saturating_sub(self.config.orphan_expiry_secs));
+            while self.tree.orphans().count() >= self.config.max_orphan_blocks {
+                if self.tree.evict_oldest_orphan().is_none() {
+                    return Err(ChainError::OrphanPoolFull);
+                }
+            }
+            self.tree.add_orphan(block, now)?;
+            return Ok(BlockStatus::Orphan);
+        }
+
+        // 返回提交区块本身的状态, 之后连接孤块引起的变化通过事件通知
+        let hash = block.hash.clone();
+        let status = self.accept_block(block)?;
+
+        // 连接以新区块为祖先的孤块, 无效的孤块直接丢弃
+        let mut parents = vec![hash];
+        while let Some(parent) = parents.pop() {
+            for orphan in self.tree.take_orphans(&parent) {
+                let orphan_hash = orphan.hash.clone();
+                if self.accept_block(orphan).is_ok() {
+                    parents.push(orphan_hash);
+                }
+            }
+        }
+        Ok(status)
+    }
+
+    fn accept_block(&mut self, block: Block) -> Result<BlockStatus, ChainError> {
+        if self.tree.contains(&block.hash) {
+            return Err(ChainError::DuplicateBlock { hash: block.hash });
+        }
+        if block.previous_hash != self.get_latest_block()?.hash
+            && self.tree.contains(&block.previous_hash)
+        {
+            return self.add_side_block(block);
+        }
+        self.validate_block(&block, &self.chain, &self.state)?;
+
+        // 余额或质押不足时状态保持不变, 写入存储失败时撤销
+        self.state.connect_block(&block)?;
+        if let Some(store) = self.store.as_mut() {
+            if let Err(err) = store.append(&block) {
+                self.state.disconnect_block(&block)?;
+                return Err(err);
+            }
+        }
+
+        // 从交易池中移除已被打包的交易和序号已被占用的交易, 其余交易按新状态重新校验
+        self.mempool.remove_included(&block);
+        let state = &self.state;
+        self.mempool
+            .retain(|transaction| transaction.nonce() >= state.nonce(transaction.sender()));
+        self.revalidate_mempool();
+        self.mempool.expire(Utc::now().timestamp());
+        self.tree
+            .insert(block.clone(), self.consensus.block_work(&block))?;
+        self.emit(ChainEvent::BlockConnected {
+            hash: block.hash.clone(),
+            index: block.index,
+        });
+        self.chain.push(block);
+        Ok(BlockStatus::Connected)
+    }
+
+    // 侧链区块只做不依赖上下文的校验就加入区块树, 侧链区块只保存在内存中
+    // 侧链累计工作量超过主链时才按所在分支完整校验并触发链重组
+    fn add_side_block(&mut self, block: Block) -> Result<BlockStatus, ChainError> {
+        let tip = self.get_latest_block()?;
+        let fork_point = self
+            .tree
+            .common_ancestor(&tip.hash, &block.previous_hash)
+            .ok_or(ChainError::BrokenLink { index: block.index })?;
+        if tip.index - fork_point.index > self.config.max_fork_depth {
+            return Err(ChainError::ForkTooDeep { index: block.index });
+        }
+        self.check_block(&block)?;
+        self.consensus.check_header(&block)?;
+
+        let work = self.consensus.block_work(&block);
+        self.tree.insert(block, work)?;
+        if self.tree.best_tip().hash == self.get_latest_block()?.hash {
+            return Ok(BlockStatus::SideChain);
+        }
+        let depth = self.reorganize()?;
+        Ok(BlockStatus::Reorganized { depth })
+    }
+
+    // 切换到区块树中累计工作量最大的分支: 断开公共祖先之后的旧区块, 连接新分支,
+    // 断开区块中的交易和原有交易池一起按新主链重新校验, 仍然有效的留在交易池中
+    // 新分支上的无效区块及其后代从区块树中移除, 主链保持不变
+    // 返回断开的区块数
+    fn reorganize(&mut self) -> Result<u64, ChainError> {
+        let old_tip = self.get_latest_block()?.hash.clone();
+        let new_tip = self.tree.best_tip().clone();
+        let (Some(new_chain), Some(fork_point)) = (
+            self.tree.chain_to(&new_tip.hash),
+            self.tree.common_ancestor(&old_tip, &new_tip.hash).cloned(),
+        ) else {
+            return Err(ChainError::BrokenLink {
+                index: new_tip.index,
+            });
+        };
+        let fork_height = fork_point.index as usize + 1;
+
+        // 新分支上的区块加入区块树时只做过不依赖上下文的校验, 从分叉点开始逐个完整校验
+        let mut state = self.state_for_branch(&new_chain[..fork_height])?;
+        for height in fork_height..new_chain.len() {
+            let block = &new_chain[height];
+            let result = self
+                .validate_block(block, &new_chain[..height], &state)
+                .and_then(|()| state.connect_block(block));
+            if let Err(err) = result {
+                self.tree.remove(&block.hash);
+                return Err(err);
+            }
+        }
+        if let Some(store) = self.store.as_mut() {
+            store.truncate(fork_height as u64)?;
+            for block in &new_chain[fork_height..] {
+                store.append(block)?;
+            }
+        }
+        let disconnected = self.chain.split_off(fork_height);
+        self.chain = new_chain;
+        self.state = state;
+
+        let mut events: Vec<ChainEvent> = disconnected
+            .iter()
+            .rev()
+            .map(|block| ChainEvent::BlockDisconnected {
+                hash: block.hash.clone(),
+                index: block.index,
+            })
+            .collect();
+        events.extend(
+            self.chain[fork_height..]
+                .iter()
+                .map(|block| ChainEvent::BlockConnected {
+                    hash: block.hash.clone(),
+                    index: block.index,
+                }),
+        );
+        let depth = disconnected.len() as u64;
+        events.push(ChainEvent::Reorganized {
+            old_tip,
+            new_tip: new_tip.hash,
+            fork_point: fork_point.hash,
+            depth,
+        });
+        for event in events {
+            self.emit(event);
+        }
+
+        let confirmed: HashSet<String> = self.chain[fork_height..]
+            .iter()
+            .flat_map(|block| block.transactions.iter().map(|tx| tx.hash()))
+            .collect();
+        let pending = self.mempool.drain();
+        let candidates = disconnected
+            .into_iter()
+            .flat_map(|block| block.transactions)
+            .filter(|transaction| !transaction.is_coinbase())
+            .chain(pending)
+            .filter(|transaction| !confirmed.contains(&transaction.hash()));
+        for transaction in candidates {
+            // 在新主链上不再有效的交易直接丢弃
+            let _ = self.add_transaction(transaction);
+        }
+        Ok(depth)
+    }
+
+    // 从主链状态出发, 断开到与 branch 的公共祖先为止的区块, 再连接 branch 上之后的区块
+    // branch 为从创世区块开始的完整分支
+    fn state_for_branch(&self, branch: &[Block]) -> Result<ChainState, ChainError> {
+        let common = self
+            .chain
+            .iter()
+            .zip(branch)
+            .take_while(|(active, block)| active.hash == block.hash)
+            .count();
+        let mut state = self.state.clone();
+        for block in self.chain[common..].iter().rev() {
+            state.disconnect_block(block)?;
+        }
+        for block in &branch[common..] {
+            state.connect_block(block)?;
+        }
+        Ok(state)
+    }
+
+    // 只接受已签名的普通交易, 挖矿奖励交易由矿工自己生成
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<(), ChainError> {
+        transaction.verify_signature()?;
+        if transaction.amount().is_zero() {
+            return Err(ChainError::InvalidAmount(transaction.amount().to_string()));
+        }
+
+        self.mempool.expire(Utc::now().timestamp());
+        let txid = transaction.hash();
+        if self.mempool.contains(&txid) {
+            return Err(ChainError::DuplicateTransaction { txid });
+        }
+
+        // 已确认的序号不能再用, 交易池中相同序号的交易只能被手续费更高的交易替换
+        let sender = transaction.sender();
+        let nonce = transaction.nonce();
+        let expected = self.state.nonce(sender);
+        if nonce < expected {
+            return Err(ChainError::InvalidNonce {
+                address: sender.to_string(),
+                expected,
+                actual: nonce,
+            });
+        }
+
+        match self.config.ledger {
+            LedgerMode::Utxo => self.check_utxo_transaction(&transaction)?,
+            LedgerMode::Account => self.check_account_transaction(&transaction)?,
+        }
+
+        let now = Utc::now().timestamp();
+        if let Some(replaced) = self.mempool.replace(transaction.clone(), now)? {
+            self.emit(ChainEvent::TransactionReplaced {
+                sender: transaction.sender().to_string(),
+                nonce: transaction.nonce(),
+                old_txid: replaced.hash(),
+                new_txid: txid,
+            });
+        }
+        Ok(())
+    }
+
+    // 按主链状态重新校验交易池: 每个 sender 的交易按序号累计支出和解除质押,
+    // 第一笔无法应用的交易和同一 sender 序号更大的交易一起移除
+    fn revalidate_mempool(&mut self) {
+        let mut invalid = Vec::new();
+        for sender in self.mempool.senders() {
+            let (balance, stake) = (self.get_balance(sender), self.get_stake(sender));
+            let (mut spend, mut unstake) = (Some(Amount::ZERO), Some(Amount::ZERO));
+            for transaction in self.mempool.from_sender(sender) {
+                let valid = match self.config.ledger {
+                    LedgerMode::Utxo => self
+                        .state
+                        .check_inputs(transaction, &HashMap::new(), &HashSet::new())
+                        .is_ok(),
+                    LedgerMode::Account => {
+                        spend = transaction
+                            .cost()
+                            .ok()
+                            .and_then(|cost| spend?.checked_add(cost));
+                        unstake = unstaked_amount(transaction)
+                            .ok()
+                            .and_then(|amount| unstake?.checked_add(amount));
+                        spend.is_some_and(|spend| spend <= balance)
+                            && unstake.is_some_and(|unstake| unstake <= stake)
+                    }
+                };
+                if !valid {
+                    invalid.push(transaction.hash());
+                    break;
+                }
+            }
+        }
+        for txid in invalid {
+            self.mempool.remove_with_descendants(&txid);
+        }
+    }
+
+    // 被替换的交易 (相同 sender 和序号) 的支出不计入交易池中的待打包支出
+    fn check_account_transaction(&self, transaction: &Transaction) -> Result<(), ChainError> {
+        if transaction.is_spend() {
+            return Err(ChainError::LedgerMismatch {
+                mode: self.config.ledger,
+            });
+        }
+        let sender = transaction.sender();
+        let replaced = Some(transaction.nonce());
+
+        // 解除质押受已确认质押的限制, 可用余额只需支付手续费
+        if transaction.kind() == TxKind::Unstake {
+            let pending_unstake = self.pending_total(sender, replaced, unstaked_amount)?;
+            let required = pending_unstake
+                .checked_add(transaction.amount())
+                .ok_or(ChainError::AmountOverflow)?;
+            let stake = self.get_stake(sender);
+            if required > stake {
+                return Err(ChainError::InsufficientStake {
+                    address: sender.to_string(),
+                    available: stake.saturating_sub(pending_unstake),
+                    required: transaction.amount(),
+                });
+            }
+        }
+
+        // 已确认余额减去交易池中尚未打包的支出, 需要覆盖金额和手续费
+        let cost = transaction.cost()?;
+        if !cost.is_zero() {
+            let pending_spend = self.pending_total(sender, replaced, Transaction::cost)?;
+            let required = pending_spend
+                .checked_add(cost)
+                .ok_or(ChainError::AmountOverflow)?;
+            let balance = self.get_balance(sender);
+            if required > balance {
+                return Err(ChainError::Overspend {
+                    address: sender.to_string(),
+                    available: balance.saturating_sub(pending_spend),
+                    required: cost,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // 输入必须是已确认的未花费输出, 且不能与交易池中的其他交易花费同一个输出
+    fn check_utxo_transaction(&self, transaction: &Transaction) -> Result<(), ChainError> {
+        let pending_inputs: HashSet<OutPoint> = self
+            .mempool
+            .transactions()
+            .into_iter()
+            .filter(|pending| {
+                pending.sender() != transaction.sender() || pending.nonce() != transaction.nonce()
+            })
+            .flat_map(|pending| pending.inputs().iter().cloned())
+            .collect();
+        self.state
+            .check_inputs(transaction, &HashMap::new(), &pending_inputs)?;
+        Ok(())
+    }
+
+    // 交易池中会减少该账户可用余额的交易总额, 包括手续费
+    pub fn get_pending_spend(&self, address: &str) -> Result<Amount, ChainError> {
+        self.pending_total(address, None, Transaction::cost)
+    }
+
+    // 交易池中该账户尚未打包的解除质押总额
+    pub fn get_pending_unstake(&self, address: &str) -> Result<Amount, ChainError> {
+        self.pending_total(address, None, unstaked_amount)
+    }
+
+    // 交易池中该账户的交易按 amount 计算的总额, 跳过序号为 exclude_nonce 的交易
+    fn pending_total(
+        &self,
+        address: &str,
+        exclude_nonce: Option<u64>,
+        amount: impl Fn(&Transaction) -> Result<Amount, ChainError>,
+    ) -> Result<Amount, ChainError> {
+        self.mempool
+            .from_sender(address)
+            .filter(|transaction| Some(transaction.nonce()) != exclude_nonce)
+            .try_fold(Amount::ZERO, |total, transaction| {
+                total
+                    .checked_add(amount(transaction)?)
+                    .ok_or(ChainError::AmountOverflow)
+            })
+    }
+
+    // 已确认的属于该地址的未花费输出
+    pub fn get_unspent(&self, address: &str) -> Vec<(OutPoint, TxOutput)> {
+        self.state.utxos().unspent_for(address)
+    }
+
+    // 已确认的质押金额
+    pub fn get_stake(&self, address: &str) -> Amount {
+        self.state.stakes().stake_of(address)
+    }
+
+    // 已确认的可用余额
+    pub fn get_balance(&self, address: &str) -> Amount {
+        self.state.balance(address)
+    }
+
+    // 已确认的交易数
+    pub fn get_nonce(&self, address: &str) -> u64 {
+        self.state.nonce(address)
+    }
+
+    // 该账户下一笔交易应使用的序号, 跳过交易池中已连续占用的序号
+    pub fn next_nonce(&self, address: &str) -> u64 {
+        let mut nonce = self.state.nonce(address);
+        while self.mempool.get_by_nonce(address, nonce).is_some() {
+            nonce += 1;
+        }
+        nonce
+    }
+
+    // 从创世区块重新计算主链状态, 不使用缓存
+    pub fn rebuild_state(&self) -> Result<ChainState, ChainError> {
+        ChainState::from_blocks(self.config.ledger, &self.chain)
+    }
+
+    pub fn is_chain_valid(&self) -> Result<(), ChainError> {
+        // 按顺序重放所有交易, 任何账户的余额和质押都不能为负
+        // 创世区块不需要校验, 但其中的初始分配和质押同样要计入
+        let genesis_block = self.chain.first().ok_or(ChainError::EmptyChain)?;
+        let mut state = ChainState::new(self.config.ledger);
+        state.connect_block(genesis_block)?;
+
+        for i in 1..self.chain.len() {
+            let current_block = &self.chain[i];
+            self.validate_block(current_block, &self.chain[..i], &state)?;
+            state.connect_block(current_block)?;
+        }
+
+        // 增量维护的状态缓存必须与重放结果一致
+        if state != self.state {
+            return Err(ChainError::InconsistentState);
+        }
+        Ok(())
+    }
+
+    // 校验区块头和交易签名, 不涉及账户余额
+    // previous_blocks 为从创世区块到父区块的链, state 为父区块处的链状态
+    fn validate_block(
+        &self,
+        block: &Block,
+        previous_blocks: &[Block],
+        state: &ChainState,
+    ) -> Result<(), ChainError> {
+        let previous_block = previous_blocks.last().ok_or(ChainError::EmptyChain)?;
+        self.check_block(block)?;
+
+        // 验证区块链接是否正确
+        if block.previous_hash != previous_block.hash || block.index != previous_block.index + 1 {
+            return Err(ChainError::BrokenLink { index: block.index });
+        }
+
+        // 时间戳不能早于最近区块的中位时间, 也不能超前当前时间太多
+        if block.timestamp < median_time_past(previous_blocks, self.config.median_time_span) {
+            return Err(ChainError::TimestampTooOld { index: block.index });
+        }
+        if block.timestamp > Utc::now().timestamp() + self.config.max_future_block_time {
+            return Err(ChainError::TimestampTooFarInFuture { index: block.index });
+        }
+
+        // 工作量证明或出块者签名由共识引擎校验
+        self.consensus.verify(block, previous_blocks, state)?;
+        Ok(())
+    }
+
+    // 不依赖父区块的校验: 哈希, 默克尔根, 交易签名和挖矿奖励
+    fn check_block(&self, block: &Block) -> Result<(), ChainError> {
+        if block.hash != block.calculate_hash() {
+            return Err(ChainError::InvalidHash { index: block.index });
+        }
+        if block.merkle_root != block.calculate_merkle_root() {
+            return Err(ChainError::InvalidMerkleRoot { index: block.index });
+        }
+
+        // 验证交易签名, 每个区块最多一笔挖矿奖励交易, 金额不能超过区块奖励加手续费
+        let max_coinbase = block
+            .transactions
+            .iter()
+            .filter(|transaction| !transaction.is_coinbase())
+            .try_fold(self.config.mining_reward, |total, transaction| {
+                total
+                    .checked_add(transaction.fee())
+                    .ok_or(ChainError::AmountOverflow)
+            })?;
+        let mut coinbase_count = 0;
+        for transaction in &block.transactions {
+            if transaction.is_coinbase() {
+                // 挖矿奖励不能带输入或显式输出, 否则输出总额不受奖励上限约束
+                if transaction.kind() != TxKind::Transfer || transaction.is_spend() {
+                    return Err(ChainError::UnexpectedCoinbase);
+                }
+                if transaction.amount() > max_coinbase {
+                    return Err(ChainError::InvalidCoinbaseAmount {
+                        index: block.index,
+                        max: max_coinbase,
+                        actual: transaction.amount(),
+                    });
+                }
+                coinbase_count += 1;
+            } else {
+                transaction.verify_signature()?;
+                if transaction.amount().is_zero() {
+                    return Err(ChainError::InvalidAmount(transaction.amount().to_string()));
+                }
+            }
+        }
+        if coinbase_count > 1 {
+            return Err(ChainError::MultipleCoinbase { index: block.index });
+        }
+
+        let regular = block.transactions.len() - coinbase_count;
+        let size: usize = block
+            .transactions
+            .iter()
+            .filter(|transaction| !transaction.is_coinbase())
+            .map(Transaction::size)
+            .sum();
+        if regular > self.config.max_block_transactions || size > self.config.max_block_size {
+            return Err(ChainError::BlockTooLarge { index: block.index });
+        }
+        Ok(())
+    }
+}
+
+// 解除质押交易退回的质押金额
+fn unstaked_amount(transaction: &Transaction) -> Result<Amount, ChainError> {
+    match transaction.kind() {
+        TxKind::Unstake => Ok(transaction.amount()),
+        _ => Ok(Amount::ZERO),
+    }
+}
+
+// 最近 span 个区块时间戳的中位数
+fn median_time_past(previous_blocks: &[Block], span: usize) -> i64 {
+    let start = previous_blocks.len().saturating_sub(span.max(1));
+    let mut timestamps: Vec<i64> = previous_blocks[start..]
+        .iter()
+        .map(|block| block.timestamp)
+        .collect();
+    timestamps.sort_unstable();
+    timestamps
+        .get(timestamps.len() / 2)
+        .copied()
+        .unwrap_or(i64::MIN)
+}
+
+// 单元测试
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{ProofOfAuthority, ProofOfStake};
+    use crate::difficulty::RetargetConfig;
+    use crate::mempool::MempoolConfig;
+    use crate::mining::ParallelMiner;
+    use crate::storage::{FileStore, MemoryStore};
+    use crate::target::Target;
+    use crate::wallet::Wallet;
+
+    fn coins(n: u64) -> Amount {
+        Amount::from_coins(n).unwrap()
+    }
+
+    fn bits(zeros: u32) -> u32 {
+        Target::from_leading_zeros(zeros).to_compact()
+    }
+
+    // 在 parent 之上挖出只含挖矿奖励的区块, 用于构造分叉
+    fn mined_child(parent: &Block, miner: &str, difficulty: u32) -> Block {
+        let reward = Transaction::coinbase(miner.to_string(), coins(100));
+        let mut block = Block::new(parent.index + 1, vec![reward], parent.hash.clone());
+        block.mine_block(difficulty);
+        block
+    }
+
+    #[test]
+    fn test_blockchain_creation() {
+        let blockchain = Blockchain::new(bits(16), coins(100));
+        assert_eq!(blockchain.chain.len(), 1); // 验证创世区块
+        assert_eq!(blockchain.get_latest_block().unwrap().bits(), bits(16));
+        assert_eq!(blockchain.mining_reward(), coins(100));
+    }
+
+    #[test]
+    fn test_mining() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        blockchain
+            .add_transaction(sender.transfer("recipient".to_string(), coins(50), 0))
+            .unwrap();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert_eq!(blockchain.chain.len(), 3);
+    }
+
+    #[test]
+    fn test_chain_validity() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        blockchain
+            .add_transaction(sender.transfer("recipient".to_string(), coins(50), 0))
+            .unwrap();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert!(blockchain.is_chain_valid().is_ok());
+    }
+
+    #[test]
+    fn test_proof_of_authority_chain() {
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        let validators = vec![alice.address(), bob.address()];
+        // 两个节点共享同一个创世区块
+        let genesis_block = Block::genesis(0);
+        let node = |signer: &Wallet| {
+            let consensus = ProofOfAuthority::new(validators.clone()).with_signer(signer.clone());
+            let mut store = MemoryStore::new();
+            store.append(&genesis_block).unwrap();
+            let config = ChainConfig::new(0, coins(100));
+            Blockchain::open_with_consensus(Box::new(store), config, Box::new(consensus)).unwrap()
+        };
+        let (mut alice_node, mut bob_node) = (node(&alice), node(&bob));
+
+        // 高度 1 轮到 bob, alice 不能出块
+        assert!(matches!(
+            alice_node.mine_pending_transactions(alice.address()),
+            Err(ChainError::NotBlockProducer { index: 1 })
+        ));
+        let block = bob_node
+            .mine_pending_transactions(bob.address())
+            .unwrap()
+            .clone();
+        assert!(block.signature().is_some());
+
+        // alice 接受 bob 签名的区块后出下一个块
+        alice_node.add_block(block).unwrap();
+        alice_node
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        assert!(alice_node.is_chain_valid().is_ok());
+        assert_eq!(alice_node.chain_work(), U256::from(3));
+        assert_eq!(alice_node.get_balance(&bob.address()), coins(100));
+
+        // 伪造的签名不能通过校验
+        let mut forged = alice_node.create_block_template(bob.address()).unwrap();
+        forged.signature = Some(alice.sign(&forged.calculate_hash_bytes()));
+        assert!(matches!(
+            alice_node.add_block(forged),
+            Err(ChainError::InvalidBlockSignature { index: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_proof_of_stake_rejects_utxo_ledger() {
+        let alice = Wallet::generate();
+        let consensus = ProofOfStake::new(b"test", vec![(alice.address(), coins(100))]);
+        let config = ChainConfig {
+            ledger: LedgerMode::Utxo,
+            ..ChainConfig::new(0, coins(10))
+        };
+        assert!(matches!(
+            Blockchain::with_consensus(config, Box::new(consensus)),
+            Err(ChainError::LedgerMismatch {
+                mode: LedgerMode::Utxo
+            })
+        ));
+    }
+
+    #[test]
+    fn test_proof_of_stake_chain() {
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        let consensus = ProofOfStake::new(b"test", vec![(alice.address(), coins(100))])
+            .with_slot_duration(1)
+            .with_signer(alice.clone());
+        let mut blockchain =
+            Blockchain::with_consensus(ChainConfig::new(0, coins(10)), Box::new(consensus))
+                .unwrap();
+        assert_eq!(blockchain.get_stake(&alice.address()), coins(100));
+        assert_eq!(blockchain.get_balance(&alice.address()), Amount::ZERO);
+
+        // alice 是唯一的质押者, 每个时隙都由她出块
+        blockchain
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        blockchain
+            .add_transaction(alice.transfer(bob.address(), coins(4), 1))
+            .unwrap();
+        blockchain
+            .add_transaction(alice.stake(coins(6), 2))
+            .unwrap();
+        blockchain
+            .add_transaction(alice.unstake(coins(50), 3))
+            .unwrap();
+
+        // 质押不能超过可用余额, 解除质押不能超过已质押金额
+        assert!(matches!(
+            blockchain.add_transaction(bob.stake(coins(5), 0)),
+            Err(ChainError::Overspend { .. })
+        ));
+        assert!(matches!(
+            blockchain.add_transaction(alice.unstake(coins(51), 4)),
+            Err(ChainError::InsufficientStake { .. })
+        ));
+
+        let block = blockchain
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        assert!(block.signature().is_some());
+        assert_eq!(blockchain.get_stake(&alice.address()), coins(56));
+        assert_eq!(blockchain.get_balance(&alice.address()), coins(60));
+        assert_eq!(blockchain.get_balance(&bob.address()), coins(4));
+        assert!(blockchain.is_chain_valid().is_ok());
+    }
+
+    #[test]
+    fn test_utxo_ledger_chain() {
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        let config = ChainConfig {
+            ledger: LedgerMode::Utxo,
+            ..ChainConfig::new(bits(8), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        blockchain
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        let (funding, _) = blockchain.get_unspent(&alice.address()).remove(0);
+
+        // 不能花费别人的输出
+        assert!(matches!(
+            blockchain.add_transaction(bob.spend(
+                vec![funding.clone()],
+                vec![TxOutput::new(bob.address(), coins(100))],
+                0,
+            )),
+            Err(ChainError::InputNotOwned { .. })
+        ));
+
+        // 支付 30 给 bob, 剩余 70 找零给自己
+        let payment = alice.spend(
+            vec![funding.clone()],
+            vec![
+                TxOutput::new(bob.address(), coins(30)),
+                TxOutput::new(alice.address(), coins(70)),
+            ],
+            0,
+        );
+        blockchain.add_transaction(payment).unwrap();
+
+        // 交易池中已花费的输出不能再次花费, 账户模式的转账也不被接受
+        let conflicting = alice.spend(
+            vec![funding.clone()],
+            vec![TxOutput::new(alice.address(), coins(100))],
+            1,
+        );
+        assert!(matches!(
+            blockchain.add_transaction(conflicting),
+            Err(ChainError::DoubleSpend { .. })
+        ));
+        assert!(matches!(
+            blockchain.add_transaction(alice.transfer(bob.address(), coins(1), 1)),
+            Err(ChainError::LedgerMismatch { .. })
+        ));
+
+        blockchain
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        assert_eq!(blockchain.get_balance(&alice.address()), coins(170));
+        assert_eq!(blockchain.get_balance(&bob.address()), coins(30));
+        assert_eq!(blockchain.get_unspent(&alice.address()).len(), 2);
+        assert!(matches!(
+            blockchain.add_transaction(alice.spend(
+                vec![funding],
+                vec![TxOutput::new(bob.address(), coins(100))],
+                1,
+            )),
+            Err(ChainError::UnknownOutput { .. })
+        ));
+        assert!(blockchain.is_chain_valid().is_ok());
+        assert_eq!(blockchain.rebuild_state().unwrap(), *blockchain.state());
+    }
+
+    // 通过序列化修改交易字段, 模拟其他节点构造的交易
+    fn tampered(transaction: &Transaction, field: &str, value: serde_json::Value) -> Transaction {
+        let mut json = serde_json::to_value(transaction).unwrap();
+        json[field] = value;
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_utxo_outputs_bounded_by_amount() {
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        let config = ChainConfig {
+            ledger: LedgerMode::Utxo,
+            ..ChainConfig::new(bits(8), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        let genesis = blockchain.chain[0].clone();
+
+        // 挖矿奖励的金额在上限之内, 但显式输出铸造了更多币
+        let minted = serde_json::to_value([TxOutput::new(alice.address(), coins(1000))]).unwrap();
+        let reward = Transaction::coinbase(alice.address(), coins(100));
+        let reward = tampered(&reward, "outputs", minted);
+        let mut block = Block::new(1, vec![reward], genesis.hash.clone());
+        block.mine_block(bits(8));
+        assert!(matches!(
+            blockchain.submit_block(block),
+            Err(ChainError::UnexpectedCoinbase)
+        ));
+        assert_eq!(blockchain.chain.len(), 1);
+
+        blockchain
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        let (funding, _) = blockchain.get_unspent(&alice.address()).remove(0);
+
+        // 签名的金额与输出总额不一致
+        let spend = alice.spend(
+            vec![funding],
+            vec![TxOutput::new(bob.address(), coins(100))],
+            0,
+        );
+        let mut spend = tampered(&spend, "amount", serde_json::to_value(coins(1)).unwrap());
+        spend.sign(&alice);
+        assert!(matches!(
+            blockchain.add_transaction(spend.clone()),
+            Err(ChainError::OutputsMismatch { .. })
+        ));
+        let tip = blockchain.get_latest_block().unwrap().clone();
+        let reward = Transaction::coinbase(bob.address(), coins(100));
+        let mut block = Block::new(2, vec![reward, spend], tip.hash.clone());
+        block.mine_block(bits(8));
+        assert!(matches!(
+            blockchain.submit_block(block),
+            Err(ChainError::OutputsMismatch { .. })
+        ));
+        assert_eq!(blockchain.chain.len(), 2);
+        assert_eq!(blockchain.get_balance(&bob.address()), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_cancelled_mining_leaves_chain_unchanged() {
+        let mut blockchain = Blockchain::new(bits(200), coins(100));
+        let sender = Wallet::generate();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let outcome = blockchain
+            .mine_pending_transactions_with(sender.address(), &cancel, None)
+            .unwrap();
+        assert!(matches!(outcome, MiningOutcome::Cancelled { .. }));
+        assert_eq!(blockchain.chain.len(), 1);
+    }
+
+    #[test]
+    fn test_block_template_mined_elsewhere() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        blockchain
+            .add_transaction(sender.transfer("recipient".to_string(), coins(10), 0))
+            .unwrap();
+
+        let mut block = blockchain
+            .create_block_template("miner".to_string())
+            .unwrap();
+        let bits = block.bits;
+        ParallelMiner::new(2).mine(&mut block, bits);
+        blockchain.add_block(block).unwrap();
+
+        assert_eq!(blockchain.chain.len(), 3);
+        assert!(blockchain.mempool.is_empty());
+    }
+
+    #[test]
+    fn test_competing_block_kept_as_side_branch() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let genesis = blockchain.chain[0].clone();
+        blockchain
+            .mine_pending_transactions("alice".to_string())
+            .unwrap();
+        let main_tip = blockchain.get_latest_block().unwrap().clone();
+
+        // 另一个矿工在创世区块上挖出了同一高度的区块
+        let rival = mined_child(&genesis, "bob", bits(8));
+        blockchain.add_block(rival.clone()).unwrap();
+
+        // 主链不变, 竞争区块作为侧链保存
+        assert_eq!(blockchain.chain.len(), 2);
+        assert_eq!(blockchain.get_latest_block().unwrap().hash, main_tip.hash);
+        assert_eq!(blockchain.get_balance("bob"), Amount::ZERO);
+        let tree = blockchain.block_tree();
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.best_tip().hash, main_tip.hash);
+        assert_eq!(tree.fork_points()[0].hash, genesis.hash);
+
+        // 侧链区块先只做不依赖上下文的校验, 哈希不满足声明的目标值时直接拒绝
+        let mut weak = Block::new(1, vec![], genesis.hash.clone());
+        weak.bits = bits(8);
+        weak.hash = weak.calculate_hash();
+        while weak.meets_target() {
+            weak.nonce += 1;
+            weak.hash = weak.calculate_hash();
+        }
+        assert!(matches!(
+            blockchain.add_block(weak),
+            Err(ChainError::InsufficientPow { index: 1 })
+        ));
+        assert!(matches!(
+            blockchain.add_block(rival),
+            Err(ChainError::DuplicateBlock { .. })
+        ));
+
+        // 目标值错误的侧链区块在所在分支超过主链时才完整校验, 失败后连同后代一起移除
+        let easier = mined_child(&genesis, "carol", bits(4));
+        assert_eq!(
+            blockchain.submit_block(easier.clone()).unwrap(),
+            BlockStatus::SideChain
+        );
+        let heavier = mined_child(&easier, "carol", bits(8));
+        assert!(matches!(
+            blockchain.submit_block(heavier.clone()),
+            Err(ChainError::InvalidDifficulty { index: 1, .. })
+        ));
+        let tree = blockchain.block_tree();
+        assert!(!tree.contains(&easier.hash) && !tree.contains(&heavier.hash));
+        assert_eq!(tree.best_tip().hash, main_tip.hash);
+        assert_eq!(blockchain.get_latest_block().unwrap().hash, main_tip.hash);
+        assert!(blockchain.is_chain_valid().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_fork_below_max_depth() {
+        let config = ChainConfig {
+            max_fork_depth: 1,
+            ..ChainConfig::new(bits(4), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        let genesis = blockchain.chain[0].clone();
+        blockchain
+            .mine_pending_transactions("alice".to_string())
+            .unwrap();
+        let first = blockchain.get_latest_block().unwrap().clone();
+        blockchain
+            .mine_pending_transactions("alice".to_string())
+            .unwrap();
+
+        // 分叉点比链尾低 1 个区块时接受, 更深的分叉拒绝
+        assert_eq!(
+            blockchain
+                .submit_block(mined_child(&first, "bob", bits(4)))
+                .unwrap(),
+            BlockStatus::SideChain
+        );
+        assert!(matches!(
+            blockchain.submit_block(mined_child(&genesis, "bob", bits(4))),
+            Err(ChainError::ForkTooDeep { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_heavier_branch_triggers_reorg() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let events = blockchain.subscribe();
+        let alice = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(alice.address())
+            .unwrap();
+        let fork_point = blockchain.get_latest_block().unwrap().clone();
+        let transfer = alice.transfer("bob".to_string(), coins(10), 0);
+        blockchain.add_transaction(transfer.clone()).unwrap();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        let old_tip = blockchain.get_latest_block().unwrap().hash.clone();
+
+        // 竞争分支在分叉点之上多挖一个区块, 不包含 alice 的转账
+        let rival1 = mined_child(&fork_point, "rival", bits(8));
+        let rival2 = mined_child(&rival1, "rival", bits(8));
+        assert_eq!(
+            blockchain.submit_block(rival1.clone()).unwrap(),
+            BlockStatus::SideChain
+        );
+        assert_eq!(blockchain.get_latest_block().unwrap().hash, old_tip);
+        assert_eq!(
+            blockchain.submit_block(rival2.clone()).unwrap(),
+            BlockStatus::Reorganized { depth: 1 }
+        );
+
+        assert_eq!(blockchain.get_latest_block().unwrap().hash, rival2.hash);
+        assert_eq!(blockchain.chain.len(), 4);
+        assert_eq!(blockchain.get_balance("bob"), Amount::ZERO);
+        assert_eq!(blockchain.get_balance("rival"), coins(200));
+        assert_eq!(blockchain.get_balance(&alice.address()), coins(100));
+        assert_eq!(blockchain.get_nonce(&alice.address()), 0);
+        // 断开区块时增量撤销的状态与从头重放的结果一致
+        assert_eq!(&blockchain.rebuild_state().unwrap(), blockchain.state());
+        assert!(blockchain.is_chain_valid().is_ok());
+
+        // 被断开区块中的转账仍然有效, 回到交易池
+        let pending: Vec<String> = blockchain
+            .pending_transactions()
+            .into_iter()
+            .map(|tx| tx.hash())
+            .collect();
+        assert_eq!(pending, [transfer.hash()]);
+
+        let events: Vec<ChainEvent> = events.try_iter().collect();
+        assert_eq!(
+            events[events.len() - 4..],
+            [
+                ChainEvent::BlockDisconnected {
+                    hash: old_tip.clone(),
+                    index: 2
+                },
+                ChainEvent::BlockConnected {
+                    hash: rival1.hash.clone(),
+                    index: 2
+                },
+                ChainEvent::BlockConnected {
+                    hash: rival2.hash.clone(),
+                    index: 3
+                },
+                ChainEvent::Reorganized {
+                    old_tip,
+                    new_tip: rival2.hash.clone(),
+                    fork_point: fork_point.hash.clone(),
+                    depth: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reorg_rewrites_store() {
+        let dir = std::env::temp_dir().join(format!("blockchain-reorg-{}", uuid::Uuid::new_v4()));
+        let config = ChainConfig::new(bits(8), coins(100));
+        let mut blockchain =
+            Blockchain::open(Box::new(FileStore::open(&dir).unwrap()), config.clone()).unwrap();
+        blockchain
+            .mine_pending_transactions("alice".to_string())
+            .unwrap();
+
+        let genesis = blockchain.chain[0].clone();
+        let rival1 = mined_child(&genesis, "rival", bits(8));
+        let rival2 = mined_child(&rival1, "rival", bits(8));
+        blockchain.add_block(rival1).unwrap();
+        blockchain.add_block(rival2.clone()).unwrap();
+        drop(blockchain);
+
+        let reopened = Blockchain::open(Box::new(FileStore::open(&dir).unwrap()), config).unwrap();
+        assert_eq!(reopened.chain.len(), 3);
+        assert_eq!(reopened.get_latest_block().unwrap().hash, rival2.hash);
+        assert_eq!(reopened.get_balance("alice"), Amount::ZERO);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_from_json_snapshot() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        blockchain
+            .add_transaction(sender.transfer("alice".to_string(), coins(30), 0))
+            .unwrap();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+
+        // 序列化的区块链可以作为快照读回, 用共识引擎重新校验后恢复
+        let json = serde_json::to_string(&blockchain).unwrap();
+        let snapshot: ChainSnapshot = serde_json::from_str(&json).unwrap();
+        let consensus = Box::new(ProofOfWork::from_config(&snapshot.config));
+        let restored = Blockchain::from_snapshot(snapshot, consensus).unwrap();
+        assert_eq!(
+            restored.get_latest_block().unwrap().hash,
+            blockchain.get_latest_block().unwrap().hash
+        );
+        assert_eq!(restored.state(), blockchain.state());
+        assert_eq!(restored.block_tree().len(), 3);
+        assert_eq!(restored.get_balance("alice"), coins(30));
+
+        // 被篡改的快照无法恢复
+        let mut snapshot = blockchain.snapshot();
+        snapshot.chain[1].nonce += 1;
+        let consensus = Box::new(ProofOfWork::from_config(&snapshot.config));
+        assert!(Blockchain::from_snapshot(snapshot, consensus).is_err());
+    }
+
+    #[test]
+    fn test_submit_block_connects_orphans() {
+        let mut producer = Blockchain::new(bits(8), coins(100));
+        producer
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        producer
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        let blocks = producer.chain.clone();
+
+        // 另一个节点从同一个创世区块开始
+        let mut store = MemoryStore::new();
+        store.append(&blocks[0]).unwrap();
+        let config = ChainConfig::new(bits(8), coins(100));
+        let mut node = Blockchain::open(Box::new(store), config).unwrap();
+
+        // 先收到高度 2 的区块, 父区块未知
+        assert_eq!(
+            node.submit_block(blocks[2].clone()).unwrap(),
+            BlockStatus::Orphan
+        );
+        assert_eq!(node.block_tree().orphans().count(), 1);
+        assert!(matches!(
+            node.submit_block(blocks[2].clone()),
+            Err(ChainError::DuplicateBlock { .. })
+        ));
+
+        // 父区块到达后两个区块都连接到主链
+        assert_eq!(
+            node.submit_block(blocks[1].clone()).unwrap(),
+            BlockStatus::Connected
+        );
+        assert_eq!(node.get_latest_block().unwrap().hash, blocks[2].hash);
+        assert_eq!(node.block_tree().orphans().count(), 0);
+        assert_eq!(node.get_balance("miner"), coins(200));
+    }
+
+    #[test]
+    fn test_submit_block_rejects_inflated_coinbase() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let genesis = blockchain.chain[0].clone();
+        let reward = Transaction::coinbase("miner".to_string(), coins(1000));
+        let mut block = Block::new(1, vec![reward], genesis.hash.clone());
+        block.mine_block(bits(8));
+
+        assert!(matches!(
+            blockchain.submit_block(block),
+            Err(ChainError::InvalidCoinbaseAmount { index: 1, .. })
+        ));
+
+        // 无效的区块也不会进入孤块池
+        let mut orphan = Block::new(2, vec![], "f".repeat(64));
+        orphan.hash = "0".repeat(64);
+        assert!(matches!(
+            blockchain.submit_block(orphan),
+            Err(ChainError::InvalidHash { index: 2 })
+        ));
+        assert_eq!(blockchain.chain.len(), 1);
+        assert_eq!(blockchain.block_tree().orphans().count(), 0);
+    }
+
+    #[test]
+    fn test_orphan_pool_checks_work_and_evicts_oldest() {
+        let config = ChainConfig {
+            retarget: RetargetConfig {
+                pow_limit: bits(4),
+                ..RetargetConfig::default()
+            },
+            max_orphan_blocks: 2,
+            ..ChainConfig::new(bits(4), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        let unknown = Block::new(5, vec![], "f".repeat(64));
+
+        // 目标值比 pow_limit 更容易的孤块
+        let easy = mined_child(&unknown, "miner", bits(1));
+        assert!(matches!(
+            blockchain.submit_block(easy),
+            Err(ChainError::InvalidDifficulty { index: 6, .. })
+        ));
+
+        // 哈希不满足声明的目标值的孤块
+        let mut weak = Block::new(6, vec![], unknown.hash.clone());
+        while weak.meets_target() {
+            weak.nonce += 1;
+            weak.hash = weak.calculate_hash();
+        }
+        assert!(matches!(
+            blockchain.submit_block(weak),
+            Err(ChainError::InsufficientPow { index: 6 })
+        ));
+        assert_eq!(blockchain.block_tree().orphans().count(), 0);
+
+        // 孤块池已满时淘汰最早到达的孤块
+        let orphans: Vec<Block> = ["a", "b", "c"]
+            .iter()
+            .map(|miner| mined_child(&unknown, miner, bits(4)))
+            .collect();
+        for orphan in &orphans {
+            assert_eq!(
+                blockchain.submit_block(orphan.clone()).unwrap(),
+                BlockStatus::Orphan
+            );
+        }
+        assert_eq!(blockchain.block_tree().orphans().count(), 2);
+        assert!(!blockchain.block_tree().is_orphan(&orphans[0].hash));
+        assert!(blockchain.block_tree().is_orphan(&orphans[2].hash));
+    }
+
+    #[test]
+    fn test_orphans_expire() {
+        let config = ChainConfig {
+            orphan_expiry_secs: -1,
+            ..ChainConfig::new(bits(4), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        let unknown = Block::new(5, vec![], "f".repeat(64));
+        let first = mined_child(&unknown, "a", bits(4));
+        let second = mined_child(&unknown, "b", bits(4));
+        blockchain.submit_block(first.clone()).unwrap();
+        blockchain.submit_block(second.clone()).unwrap();
+
+        // 过期时间为负数时, 之前到达的孤块在下一个孤块到达时即被移除
+        assert!(!blockchain.block_tree().is_orphan(&first.hash));
+        assert!(blockchain.block_tree().is_orphan(&second.hash));
+    }
+
+    #[test]
+    fn test_overspend_rejected() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+
+        blockchain
+            .add_transaction(sender.transfer("recipient".to_string(), coins(60), 0))
+            .unwrap();
+        // 交易池中已有 60 的支出, 剩余可用 40
+        assert!(matches!(
+            blockchain.add_transaction(sender.transfer("recipient".to_string(), coins(50), 1)),
+            Err(ChainError::Overspend { .. })
+        ));
+        assert_eq!(blockchain.mempool.len(), 1);
+    }
+
+    #[test]
+    fn test_out_of_order_nonce_waits_for_gap() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+
+        // 序号 1 先到达, 在序号 0 打包之前不会进入区块
+        let second = sender.transfer("recipient".to_string(), coins(20), 1);
+        blockchain.add_transaction(second).unwrap();
+        assert_eq!(blockchain.next_nonce(&sender.address()), 0);
+        let block = blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(blockchain.mempool.len(), 1);
+
+        let first = sender.transfer("recipient".to_string(), coins(10), 0);
+        blockchain.add_transaction(first.clone()).unwrap();
+        assert_eq!(blockchain.next_nonce(&sender.address()), 2);
+        assert!(matches!(
+            blockchain.add_transaction(sender.transfer("other".to_string(), coins(5), 1)),
+            Err(ChainError::ReplacementFeeTooLow { .. })
+        ));
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert!(blockchain.mempool.is_empty());
+        assert_eq!(blockchain.get_nonce(&sender.address()), 2);
+        assert_eq!(blockchain.get_balance("recipient"), coins(30));
+
+        // 已确认的交易不能被重放
+        assert!(matches!(
+            blockchain.add_transaction(first),
+            Err(ChainError::InvalidNonce {
+                expected: 2,
+                actual: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let config = ChainConfig {
+            mempool: MempoolConfig {
+                min_fee_increment: coins(1),
+                ..MempoolConfig::default()
+            },
+            ..ChainConfig::new(bits(4), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        let events = blockchain.subscribe();
+
+        let original = sender.transfer_with_fee("recipient".to_string(), coins(90), coins(1), 0);
+        blockchain.add_transaction(original.clone()).unwrap();
+
+        // 手续费增加不足 min_fee_increment 时保留原交易
+        assert!(matches!(
+            blockchain.add_transaction(sender.transfer_with_fee(
+                "recipient".to_string(),
+                coins(90),
+                Amount::from_base_units(150_000_000),
+                0
+            )),
+            Err(ChainError::ReplacementFeeTooLow { .. })
+        ));
+        assert!(blockchain.mempool.contains(&original.hash()));
+
+        // 原交易的支出不再计入, 替换交易只需与已确认余额比较
+        let replacement = sender.transfer_with_fee("recipient".to_string(), coins(90), coins(2), 0);
+        blockchain.add_transaction(replacement.clone()).unwrap();
+        assert_eq!(blockchain.mempool.len(), 1);
+        assert!(blockchain.mempool.contains(&replacement.hash()));
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [ChainEvent::TransactionReplaced {
+                sender: sender.address(),
+                nonce: 0,
+                old_txid: original.hash(),
+                new_txid: replacement.hash(),
+            }]
+        );
+
+        let block = blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert_eq!(block.transactions[0].hash(), replacement.hash());
+        assert_eq!(blockchain.get_balance("miner"), coins(102));
+        assert_eq!(blockchain.get_balance(&sender.address()), coins(8));
+    }
+
+    #[test]
+    fn test_external_block_revalidates_mempool() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        blockchain
+            .add_transaction(sender.transfer("alice".to_string(), coins(10), 0))
+            .unwrap();
+        let pending = sender.transfer("bob".to_string(), coins(80), 1);
+        blockchain.add_transaction(pending.clone()).unwrap();
+
+        // 其他节点打包了使用同一序号的冲突交易, 剩余余额不足以支付后续交易
+        let conflicting = sender.transfer("carol".to_string(), coins(90), 0);
+        let tip = blockchain.get_latest_block().unwrap().clone();
+        let reward = Transaction::coinbase("miner".to_string(), coins(100));
+        let mut block = Block::new(2, vec![conflicting, reward], tip.hash.clone());
+        block.mine_block(bits(4));
+        assert_eq!(
+            blockchain.submit_block(block).unwrap(),
+            BlockStatus::Connected
+        );
+        assert!(blockchain.mempool.is_empty());
+
+        // 绕过校验直接放入交易池的无效交易也不会被打包
+        blockchain.mempool.insert(pending, 0).unwrap();
+        let block = blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(blockchain.get_balance(&sender.address()), coins(10));
+    }
+
+    #[test]
+    fn test_block_assembly_prefers_higher_fees() {
+        let config = ChainConfig {
+            max_block_transactions: 2,
+            ..ChainConfig::new(bits(4), coins(100))
+        };
+        let mut blockchain = Blockchain::with_config(config);
+        let funder = Wallet::generate();
+        let senders = [Wallet::generate(), Wallet::generate(), Wallet::generate()];
+        blockchain
+            .mine_pending_transactions(funder.address())
+            .unwrap();
+        for (nonce, sender) in senders.iter().enumerate() {
+            blockchain
+                .add_transaction(funder.transfer(sender.address(), coins(10), nonce as u64))
+                .unwrap();
+        }
+        // 每个区块最多两笔, 第三笔转账留到下一个区块
+        for _ in 0..2 {
+            blockchain
+                .mine_pending_transactions("miner".to_string())
+                .unwrap();
+        }
+
+        // 手续费和金额一起从可用余额中扣除
+        assert!(matches!(
+            blockchain.add_transaction(senders[0].transfer_with_fee(
+                "recipient".to_string(),
+                coins(10),
+                coins(1),
+                0
+            )),
+            Err(ChainError::Overspend { .. })
+        ));
+        for (fee, sender) in [1, 3, 2].into_iter().zip(&senders) {
+            blockchain
+                .add_transaction(sender.transfer_with_fee(
+                    "recipient".to_string(),
+                    coins(1),
+                    coins(fee),
+                    0,
+                ))
+                .unwrap();
+        }
+
+        // 只能打包两笔, 手续费最低的交易留在交易池中
+        let block = blockchain
+            .mine_pending_transactions("fee-miner".to_string())
+            .unwrap();
+        let fees: Vec<Amount> = block.transactions.iter().map(|tx| tx.fee()).collect();
+        assert_eq!(fees, vec![coins(3), coins(2), Amount::ZERO]);
+        assert_eq!(blockchain.get_balance("fee-miner"), coins(105));
+        assert_eq!(blockchain.get_balance(&senders[1].address()), coins(6));
+        assert_eq!(blockchain.mempool.len(), 1);
+        assert_eq!(
+            blockchain.pending_transactions()[0].sender(),
+            senders[0].address()
+        );
+        assert!(blockchain.is_chain_valid().is_ok());
+    }
+
+    #[test]
+    fn test_zero_amount_rejected() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let sender = Wallet::generate();
+        blockchain
+            .mine_pending_transactions(sender.address())
+            .unwrap();
+        assert!(matches!(
+            blockchain.add_transaction(sender.transfer("recipient".to_string(), Amount::ZERO, 0)),
+            Err(ChainError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn test_negative_balance_invalidates_chain() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let sender = Wallet::generate();
+
+        // 绕过交易池直接打包一笔透支交易
+        let overspend = sender.transfer("recipient".to_string(), coins(50), 0);
+        let mut block = Block::new(
+            1,
+            vec![overspend],
+            blockchain.get_latest_block().unwrap().hash.clone(),
+        );
+        block.mine_block(bits(4));
+        blockchain.chain.push(block);
+
+        assert!(matches!(
+            blockchain.is_chain_valid(),
+            Err(ChainError::Overspend { .. })
+        ));
+    }
+
+    #[test]
+    fn test_chain_work_accumulates() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let genesis_work = blockchain.chain_work();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        assert_eq!(blockchain.chain_work(), genesis_work * 3);
+    }
+
+    #[test]
+    fn test_tampered_block_rejected() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+
+        blockchain.chain[1].nonce += 1;
+        assert!(matches!(
+            blockchain.is_chain_valid(),
+            Err(ChainError::InvalidHash { index: 1 })
+        ));
+
+        blockchain.chain[1].hash = blockchain.chain[1].calculate_hash();
+        assert!(matches!(
+            blockchain.is_chain_valid(),
+            Err(ChainError::BrokenLink { index: 2 })
+                | Err(ChainError::InsufficientPow { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_tampered_transaction_breaks_merkle_root() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+
+        blockchain.chain[1].transactions[0] =
+            Transaction::coinbase("thief".to_string(), coins(100));
+        assert!(matches!(
+            blockchain.is_chain_valid(),
+            Err(ChainError::InvalidMerkleRoot { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_forged_chain_without_pow_rejected() {
+        let mut blockchain = Blockchain::new(bits(12), coins(100));
+        // 不挖矿直接构造区块, 只填写正确的难度和链接
+        for index in 1..=3 {
+            let previous_hash = blockchain.get_latest_block().unwrap().hash.clone();
+            let mut block = Block::new(index, vec![], previous_hash);
+            block.bits = bits(12);
+            block.hash = block.calculate_hash();
+            while block.meets_target() {
+                block.nonce += 1;
+                block.hash = block.calculate_hash();
+            }
+            blockchain.chain.push(block);
+        }
+
+        assert!(matches!(
+            blockchain.is_chain_valid(),
+            Err(ChainError::InsufficientPow { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_block_timestamp_bounds() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        for _ in 0..3 {
+            blockchain
+                .mine_pending_transactions("miner".to_string())
+                .unwrap();
+        }
+        let previous_hash = blockchain.get_latest_block().unwrap().hash.clone();
+
+        let mut old_block = Block::new(4, vec![], previous_hash.clone());
+        old_block.timestamp = blockchain.chain[0].timestamp - 1;
+        old_block.mine_block(bits(4));
+        assert!(matches!(
+            blockchain.add_block(old_block),
+            Err(ChainError::TimestampTooOld { index: 4 })
+        ));
+
+        let mut future_block = Block::new(4, vec![], previous_hash);
+        future_block.timestamp = Utc::now().timestamp() + 3 * 60 * 60;
+        future_block.mine_block(bits(4));
+        assert!(matches!(
+            blockchain.add_block(future_block),
+            Err(ChainError::TimestampTooFarInFuture { index: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_add_block_rejects_wrong_difficulty() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let previous_hash = blockchain.get_latest_block().unwrap().hash.clone();
+        let mut block = Block::new(1, vec![], previous_hash);
+        block.mine_block(bits(4));
+
+        assert!(matches!(
+            blockchain.add_block(block),
+            Err(ChainError::InvalidDifficulty {
+                index: 1,
+                expected,
+                actual
+            }) if expected == bits(8) && actual == bits(4)
+        ));
+    }
+
+    #[test]
+    fn test_add_block_rejects_unmined_block() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let previous_hash = blockchain.get_latest_block().unwrap().hash.clone();
+        let mut block = Block::new(1, vec![], previous_hash);
+        block.bits = bits(8);
+        block.hash = block.calculate_hash();
+        // 保证 nonce 为 0 的哈希不满足难度
+        while block.meets_target() {
+            block.timestamp += 1;
+            block.hash = block.calculate_hash();
+        }
+
+        assert!(matches!(
+            blockchain.add_block(block),
+            Err(ChainError::InsufficientPow { index: 1 })
+        ));
+        assert_eq!(blockchain.chain.len(), 1);
+    }
+
+    #[test]
+    fn test_open_restores_chain_from_store() {
+        let dir = std::env::temp_dir().join(format!("blockchain-chain-{}", uuid::Uuid::new_v4()));
+        let miner = Wallet::generate();
+        let tip = {
+            let store = Box::new(FileStore::open(&dir).unwrap());
+            let mut blockchain =
+                Blockchain::open(store, ChainConfig::new(bits(4), coins(100))).unwrap();
+            blockchain
+                .mine_pending_transactions(miner.address())
+                .unwrap();
+            blockchain
+                .mine_pending_transactions(miner.address())
+                .unwrap();
+            blockchain.get_latest_block().unwrap().hash.clone()
+        };
+
+        let store = Box::new(FileStore::open(&dir).unwrap());
+        let mut blockchain =
+            Blockchain::open(store, ChainConfig::new(bits(4), coins(100))).unwrap();
+        assert_eq!(blockchain.chain.len(), 3);
+        assert_eq!(blockchain.get_latest_block().unwrap().hash, tip);
+        assert_eq!(blockchain.get_balance(&miner.address()), coins(200));
+
+        // 重启后继续出块
+        blockchain
+            .mine_pending_transactions(miner.address())
+            .unwrap();
+        assert_eq!(blockchain.chain.len(), 4);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unsigned_transaction_rejected() {
+        let mut blockchain = Blockchain::new(bits(8), coins(100));
+        let sender = Wallet::generate();
+        let forged = Transaction::new(sender.address(), "thief".to_string(), coins(50));
+        assert!(matches!(
+            blockchain.add_transaction(forged),
+            Err(ChainError::MissingSignature)
+        ));
+        assert!(matches!(
+            blockchain.add_transaction(Transaction::coinbase("thief".to_string(), coins(50))),
+            Err(ChainError::UnexpectedCoinbase)
+        ));
+        assert!(blockchain.mempool.is_empty());
+    }
+
+    #[test]
+    fn test_forged_signature_invalidates_chain() {
+        let mut blockchain = Blockchain::new(bits(4), coins(100));
+        let victim = Wallet::generate();
+        let thief = Wallet::generate();
+        blockchain
+            .mine_pending_transactions("miner".to_string())
+            .unwrap();
+
+        // 用别人的私钥为 victim 的转账签名
+        let mut forged = Transaction::new(victim.address(), thief.address(), coins(50));
+        forged.sign(&thief);
+        let mut block = Block::new(
+            2,
+            vec![forged],
+            blockchain.get_latest_block().unwrap().hash.clone(),
+        );
+        block.mine_block(bits(4));
+        blockchain.chain.push(block);
+
+        assert!(matches!(
+            blockchain.is_chain_valid(),
+            Err(ChainError::InvalidSignature)
+        ));
+    }
+}
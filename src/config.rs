@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::difficulty::RetargetConfig;
+use crate::mempool::MempoolConfig;
+
+// 账本模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LedgerMode {
+    // 账户余额模型, 交易直接从 sender 转给 recipient
+    #[default]
+    Account,
+    // UTXO 模型, 交易花费已有输出并创建新输出
+    Utxo,
+}
+
+// 区块链参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainConfig {
+    // 创世区块之后的初始目标值 (紧凑表示)
+    pub initial_bits: u32,
+    pub mining_reward: Amount,
+    pub retarget: RetargetConfig,
+    // 计算中位时间时参考的最近区块数
+    pub median_time_span: usize,
+    // 区块时间戳允许超前当前时间的秒数
+    pub max_future_block_time: i64,
+    // 挖矿使用的线程数
+    pub mining_threads: usize,
+    // 孤块池最多保存的区块数
+    pub max_orphan_blocks: usize,
+    // 孤块在孤块池中最多停留的秒数
+    pub orphan_expiry_secs: i64,
+    // 侧链的分叉点最多比主链链尾低多少个区块, 更深的分叉直接拒绝
+    pub max_fork_depth: u64,
+    // 每个区块最多打包的普通交易数, 不含挖矿奖励交易
+    pub max_block_transactions: usize,
+    // 每个区块中普通交易编码后的总字节数上限
+    pub max_block_size: usize,
+    pub mempool: MempoolConfig,
+    // 账本模式, 同一条链上的所有节点必须一致
+    pub ledger: LedgerMode,
+}
+
+impl ChainConfig {
+    pub fn new(initial_bits: u32, mining_reward: Amount) -> Self {
+        ChainConfig {
+            initial_bits,
+            mining_reward,
+            retarget: RetargetConfig::default(),
+            median_time_span: 11,
+            max_future_block_time: 2 * 60 * 60,
+            mining_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            max_orphan_blocks: 100,
+            orphan_expiry_secs: 60 * 60,
+            max_fork_depth: 100,
+            max_block_transactions: 1000,
+            max_block_size: 1_000_000,
+            mempool: MempoolConfig::default(),
+            ledger: LedgerMode::Account,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use primitive_types::U256;
+use std::fmt;
+
+use crate::block::Block;
+use crate::config::ChainConfig;
+use crate::error::ChainError;
+use crate::mining::{CancellationToken, MiningProgress, MiningStats};
+use crate::state::ChainState;
+
+pub mod poa;
+pub mod pos;
+pub mod pow;
+
+pub use poa::ProofOfAuthority;
+pub use pos::ProofOfStake;
+pub use pow::ProofOfWork;
+
+// 封装区块的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SealOutcome {
+    Sealed(MiningStats),
+    Cancelled(MiningStats),
+}
+
+// 共识引擎: 决定区块由谁产生以及如何证明区块有效
+// previous_blocks 均为从创世区块到父区块的链, state 为父区块处的链状态
+pub trait ConsensusEngine: fmt::Debug + Send + Sync {
+    // 新链的创世区块
+    fn genesis(&self, config: &ChainConfig) -> Block {
+        Block::genesis(config.initial_bits)
+    }
+
+    // 填写区块头中由共识决定的字段, 完成后重新计算区块哈希
+    fn prepare(&self, block: &mut Block, previous_blocks: &[Block]) -> Result<(), ChainError>;
+
+    // 封装已准备好的区块, 成功后区块即可通过 verify
+    fn seal(
+        &self,
+        block: &mut Block,
+        previous_blocks: &[Block],
+        state: &ChainState,
+        cancel: &CancellationToken,
+        progress: Option<&mut dyn FnMut(MiningProgress)>,
+    ) -> Result<SealOutcome, ChainError>;
+
+    // 校验区块的封装, 区块哈希、默克尔根和交易由链单独校验
+    fn verify(
+        &self,
+        block: &Block,
+        previous_blocks: &[Block],
+        state: &ChainState,
+    ) -> Result<(), ChainError>;
+
+    // 不依赖父区块的封装校验, 用于在暂存孤块前过滤无效区块
+    fn check_header(&self, _block: &Block) -> Result<(), ChainError> {
+        Ok(())
+    }
+
+    // 区块对链权重的贡献, 用于累计链的工作量
+    fn block_work(&self, block: &Block) -> U256;
+}
@@ -0,0 +1,168 @@
+use primitive_types::U256;
+use std::time::Instant;
+
+use crate::block::Block;
+use crate::consensus::{ConsensusEngine, SealOutcome};
+use crate::error::ChainError;
+use crate::mining::{CancellationToken, MiningProgress, MiningStats};
+use crate::state::ChainState;
+use crate::wallet::{self, Wallet};
+
+// 权威证明: 固定的验证者集合按区块高度轮流签名出块
+#[derive(Debug, Clone)]
+pub struct ProofOfAuthority {
+    validators: Vec<String>,
+    // 本节点的验证者私钥, 只校验区块时不需要
+    signer: Option<Wallet>,
+}
+
+impl ProofOfAuthority {
+    pub fn new(validators: Vec<String>) -> Self {
+        ProofOfAuthority {
+            validators,
+            signer: None,
+        }
+    }
+
+    // 使用 signer 为轮到它的区块签名
+    pub fn with_signer(mut self, signer: Wallet) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn validators(&self) -> &[String] {
+        &self.validators
+    }
+
+    // 高度为 index 的区块应由哪个验证者签名
+    pub fn proposer(&self, index: u64) -> Option<&str> {
+        if self.validators.is_empty() {
+            return None;
+        }
+        let position = (index % self.validators.len() as u64) as usize;
+        Some(&self.validators[position])
+    }
+}
+
+impl ConsensusEngine for ProofOfAuthority {
+    // 不需要工作量证明, 目标值和 nonce 固定为 0
+    fn prepare(&self, block: &mut Block, _previous_blocks: &[Block]) -> Result<(), ChainError> {
+        block.bits = 0;
+        block.nonce = 0;
+        block.hash = block.calculate_hash();
+        Ok(())
+    }
+
+    fn seal(
+        &self,
+        block: &mut Block,
+        _previous_blocks: &[Block],
+        _state: &ChainState,
+        _cancel: &CancellationToken,
+        _progress: Option<&mut dyn FnMut(MiningProgress)>,
+    ) -> Result<SealOutcome, ChainError> {
+        let started = Instant::now();
+        let index = block.index;
+        let signer = self
+            .signer
+            .as_ref()
+            .filter(|signer| self.proposer(index) == Some(signer.address().as_str()))
+            .ok_or(ChainError::NotBlockProducer { index })?;
+
+        block.hash = block.calculate_hash();
+        block.signature = Some(signer.sign(&block.calculate_hash_bytes()));
+        Ok(SealOutcome::Sealed(MiningStats {
+            hashes: 0,
+            elapsed: started.elapsed(),
+        }))
+    }
+
+    // 出块者只由高度决定, 不需要父区块和链状态
+    fn verify(
+        &self,
+        block: &Block,
+        _previous_blocks: &[Block],
+        _state: &ChainState,
+    ) -> Result<(), ChainError> {
+        self.check_header(block)
+    }
+
+    // 区块签名覆盖区块头哈希, 必须来自轮到的验证者
+    fn check_header(&self, block: &Block) -> Result<(), ChainError> {
+        let index = block.index;
+        let invalid = ChainError::InvalidBlockSignature { index };
+        let (Some(proposer), Some(signature)) = (self.proposer(index), block.signature.as_ref())
+        else {
+            return Err(invalid);
+        };
+        wallet::verify_signature(proposer, &block.calculate_hash_bytes(), signature)
+            .map_err(|_| invalid)
+    }
+
+    // 每个区块的权重相同
+    fn block_work(&self, _block: &Block) -> U256 {
+        U256::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sealed_block(engine: &ProofOfAuthority, index: u64) -> Result<Block, ChainError> {
+        let mut block = Block::new(index, vec![], "0".repeat(64));
+        engine.prepare(&mut block, &[])?;
+        engine.seal(
+            &mut block,
+            &[],
+            &ChainState::default(),
+            &CancellationToken::new(),
+            None,
+        )?;
+        Ok(block)
+    }
+
+    #[test]
+    fn test_validators_sign_in_round_robin() {
+        let wallets = [Wallet::generate(), Wallet::generate()];
+        let validators: Vec<String> = wallets.iter().map(Wallet::address).collect();
+        let verifier = ProofOfAuthority::new(validators.clone());
+
+        for (index, wallet) in [(1, &wallets[1]), (2, &wallets[0]), (3, &wallets[1])] {
+            let engine = ProofOfAuthority::new(validators.clone()).with_signer(wallet.clone());
+            let block = sealed_block(&engine, index).unwrap();
+            assert!(verifier.verify(&block, &[], &ChainState::default()).is_ok());
+        }
+
+        // 没轮到的验证者不能出块
+        let engine = ProofOfAuthority::new(validators).with_signer(wallets[0].clone());
+        assert!(matches!(
+            sealed_block(&engine, 1),
+            Err(ChainError::NotBlockProducer { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_block_signed_by_outsider_rejected() {
+        let validator = Wallet::generate();
+        let outsider = Wallet::generate();
+        let verifier = ProofOfAuthority::new(vec![validator.address()]);
+
+        // 外部节点把自己当作验证者签名, 也不能通过校验
+        let engine = ProofOfAuthority::new(vec![outsider.address()]).with_signer(outsider);
+        let block = sealed_block(&engine, 1).unwrap();
+        assert!(matches!(
+            verifier.verify(&block, &[], &ChainState::default()),
+            Err(ChainError::InvalidBlockSignature { index: 1 })
+        ));
+
+        // 签名后修改区块头会使签名失效
+        let engine = ProofOfAuthority::new(vec![validator.address()]).with_signer(validator);
+        let mut block = sealed_block(&engine, 1).unwrap();
+        block.timestamp += 1;
+        block.hash = block.calculate_hash();
+        assert!(verifier
+            .verify(&block, &[], &ChainState::default())
+            .is_err());
+    }
+}
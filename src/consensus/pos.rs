@@ -0,0 +1,263 @@
+use chrono::Utc;
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::amount::Amount;
+use crate::block::Block;
+use crate::config::ChainConfig;
+use crate::consensus::{ConsensusEngine, SealOutcome};
+use crate::error::ChainError;
+use crate::mining::{CancellationToken, MiningProgress, MiningStats};
+use crate::stake::StakeTable;
+use crate::state::ChainState;
+use crate::tx::Transaction;
+use crate::wallet::{self, Wallet};
+
+// 封装时最多向后查找多少个时隙
+const MAX_SLOT_LOOKAHEAD: u64 = 4096;
+// 等待时隙开始时检查取消令牌的间隔
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// 校验时允许区块时隙比本地时钟提前开始的秒数
+const MAX_SLOT_DRIFT: i64 = 2;
+
+// 随机信标: sha256(seed || slot), 所有节点对同一时隙得到相同的随机数
+pub fn beacon(seed: &[u8], slot: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(seed);
+    hasher.update(slot.to_le_bytes());
+    hasher.finalize().into()
+}
+
+// 权益证明: 时间按 slot_duration 划分为时隙, 每个时隙由信标按质押权重选出一个提议者
+// 质押表取自父区块处的链状态, 创世区块中包含初始质押
+#[derive(Debug, Clone)]
+pub struct ProofOfStake {
+    seed: Vec<u8>,
+    // 时隙长度 (秒)
+    slot_duration: i64,
+    genesis_stakes: Vec<(String, Amount)>,
+    signer: Option<Wallet>,
+}
+
+impl ProofOfStake {
+    pub fn new(seed: &[u8], genesis_stakes: Vec<(String, Amount)>) -> Self {
+        ProofOfStake {
+            seed: seed.to_vec(),
+            slot_duration: 5,
+            genesis_stakes,
+            signer: None,
+        }
+    }
+
+    pub fn with_slot_duration(mut self, seconds: i64) -> Self {
+        self.slot_duration = seconds.max(1);
+        self
+    }
+
+    // 使用 signer 在轮到它的时隙出块
+    pub fn with_signer(mut self, signer: Wallet) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn slot_duration(&self) -> i64 {
+        self.slot_duration
+    }
+
+    // 时间戳所在的时隙
+    pub fn slot(&self, timestamp: i64) -> u64 {
+        (timestamp.max(0) / self.slot_duration) as u64
+    }
+
+    // 给定质押表时该时隙的提议者
+    pub fn proposer<'a>(&self, stakes: &'a StakeTable, slot: u64) -> Option<&'a str> {
+        stakes.select_proposer(&beacon(&self.seed, slot))
+    }
+}
+
+impl ConsensusEngine for ProofOfStake {
+    // 创世区块为每个初始验证者发放并锁定对应金额
+    fn genesis(&self, _config: &ChainConfig) -> Block {
+        let transactions = self
+            .genesis_stakes
+            .iter()
+            .flat_map(|(address, amount)| {
+                [
+                    Transaction::coinbase(address.clone(), *amount),
+                    Transaction::stake(address.clone(), *amount),
+                ]
+            })
+            .collect();
+        Block::new(0, transactions, "0".repeat(64))
+    }
+
+    // 不需要工作量证明, 目标值和 nonce 固定为 0
+    fn prepare(&self, block: &mut Block, _previous_blocks: &[Block]) -> Result<(), ChainError> {
+        block.bits = 0;
+        block.nonce = 0;
+        block.hash = block.calculate_hash();
+        Ok(())
+    }
+
+    // 找到不早于区块时间戳且轮到本节点的时隙, 等到时隙开始后签名
+    fn seal(
+        &self,
+        block: &mut Block,
+        previous_blocks: &[Block],
+        state: &ChainState,
+        cancel: &CancellationToken,
+        _progress: Option<&mut dyn FnMut(MiningProgress)>,
+    ) -> Result<SealOutcome, ChainError> {
+        let started = Instant::now();
+        let index = block.index;
+        let parent = previous_blocks.last().ok_or(ChainError::EmptyChain)?;
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or(ChainError::NotBlockProducer { index })?;
+        let address = signer.address();
+        let stakes = state.stakes();
+
+        let first_slot = self
+            .slot(block.timestamp)
+            .max(self.slot(parent.timestamp) + 1);
+        let slot = (first_slot..first_slot + MAX_SLOT_LOOKAHEAD)
+            .find(|slot| self.proposer(stakes, *slot) == Some(address.as_str()))
+            .ok_or(ChainError::NotBlockProducer { index })?;
+
+        let slot_start = slot as i64 * self.slot_duration;
+        while Utc::now().timestamp() < slot_start {
+            if cancel.is_cancelled() {
+                return Ok(SealOutcome::Cancelled(MiningStats {
+                    hashes: 0,
+                    elapsed: started.elapsed(),
+                }));
+            }
+            thread::sleep(SLOT_POLL_INTERVAL);
+        }
+
+        block.timestamp = block.timestamp.max(slot_start);
+        block.hash = block.calculate_hash();
+        block.signature = Some(signer.sign(&block.calculate_hash_bytes()));
+        Ok(SealOutcome::Sealed(MiningStats {
+            hashes: 0,
+            elapsed: started.elapsed(),
+        }))
+    }
+
+    // 每个时隙最多一个区块, 签名必须来自该时隙的提议者
+    // 时隙必须已经开始, 否则提议者可以提前占用未来的时隙让其他节点等待
+    fn verify(
+        &self,
+        block: &Block,
+        previous_blocks: &[Block],
+        state: &ChainState,
+    ) -> Result<(), ChainError> {
+        let index = block.index;
+        let parent = previous_blocks.last().ok_or(ChainError::EmptyChain)?;
+        let slot = self.slot(block.timestamp);
+        if slot <= self.slot(parent.timestamp) {
+            return Err(ChainError::InvalidSlot { index });
+        }
+        let slot_start = (slot as i64).saturating_mul(self.slot_duration);
+        if slot_start > Utc::now().timestamp() + MAX_SLOT_DRIFT {
+            return Err(ChainError::FutureSlot { index });
+        }
+
+        let invalid = ChainError::InvalidBlockSignature { index };
+        let (Some(proposer), Some(signature)) = (
+            self.proposer(state.stakes(), slot),
+            block.signature.as_ref(),
+        ) else {
+            return Err(invalid);
+        };
+        wallet::verify_signature(proposer, &block.calculate_hash_bytes(), signature)
+            .map_err(|_| invalid)
+    }
+
+    // 每个区块的权重相同
+    fn block_work(&self, _block: &Block) -> U256 {
+        U256::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LedgerMode;
+
+    #[test]
+    fn test_beacon_is_deterministic_per_slot() {
+        assert_eq!(beacon(b"seed", 7), beacon(b"seed", 7));
+        assert_ne!(beacon(b"seed", 7), beacon(b"seed", 8));
+        assert_ne!(beacon(b"seed", 7), beacon(b"other", 7));
+    }
+
+    #[test]
+    fn test_only_slot_proposer_can_sign() {
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        let stake = Amount::from_coins(10).unwrap();
+        let engine = ProofOfStake::new(
+            b"test",
+            vec![(alice.address(), stake), (bob.address(), stake)],
+        );
+        let mut genesis = engine.genesis(&ChainConfig::new(0, Amount::ZERO));
+        genesis.timestamp -= 60;
+        genesis.hash = genesis.calculate_hash();
+        let state =
+            ChainState::from_blocks(LedgerMode::Account, std::slice::from_ref(&genesis)).unwrap();
+        let stakes = state.stakes();
+        assert_eq!(stakes.stake_of(&alice.address()), stake);
+
+        // 取一个已经开始的时隙, 由非提议者签名
+        let slot = engine.slot(genesis.timestamp) + 1;
+        let proposer = engine.proposer(stakes, slot).unwrap();
+        let outsider = if proposer == alice.address() {
+            &bob
+        } else {
+            &alice
+        };
+        let mut block = Block::new(1, vec![], genesis.hash.clone());
+        block.timestamp = slot as i64 * engine.slot_duration();
+        block.hash = block.calculate_hash();
+        block.signature = Some(outsider.sign(&block.calculate_hash_bytes()));
+
+        let previous = [genesis];
+        assert!(matches!(
+            engine.verify(&block, &previous, &state),
+            Err(ChainError::InvalidBlockSignature { index: 1 })
+        ));
+
+        // 同一时隙内不能再出块
+        block.timestamp = previous[0].timestamp;
+        block.hash = block.calculate_hash();
+        assert!(matches!(
+            engine.verify(&block, &previous, &state),
+            Err(ChainError::InvalidSlot { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_slot_that_has_not_started() {
+        let alice = Wallet::generate();
+        let engine = ProofOfStake::new(
+            b"test",
+            vec![(alice.address(), Amount::from_coins(10).unwrap())],
+        );
+        let genesis = engine.genesis(&ChainConfig::new(0, Amount::ZERO));
+        let state =
+            ChainState::from_blocks(LedgerMode::Account, std::slice::from_ref(&genesis)).unwrap();
+
+        // alice 是唯一的质押者, 签名有效但时隙在一小时之后
+        let mut block = Block::new(1, vec![], genesis.hash.clone());
+        block.timestamp = Utc::now().timestamp() + 60 * 60;
+        block.hash = block.calculate_hash();
+        block.signature = Some(alice.sign(&block.calculate_hash_bytes()));
+        assert!(matches!(
+            engine.verify(&block, &[genesis], &state),
+            Err(ChainError::FutureSlot { index: 1 })
+        ));
+    }
+}
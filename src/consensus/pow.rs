@@ -0,0 +1,104 @@
+use primitive_types::U256;
+
+use crate::block::Block;
+use crate::config::ChainConfig;
+use crate::consensus::{ConsensusEngine, SealOutcome};
+use crate::difficulty::RetargetConfig;
+use crate::error::ChainError;
+use crate::mining::{CancellationToken, MiningProgress, ParallelMiner};
+use crate::state::ChainState;
+use crate::target::Target;
+
+// SHA-256 工作量证明, 目标值按 RetargetConfig 调整
+#[derive(Debug, Clone)]
+pub struct ProofOfWork {
+    retarget: RetargetConfig,
+    miner: ParallelMiner,
+}
+
+impl ProofOfWork {
+    pub fn new(retarget: RetargetConfig, miner: ParallelMiner) -> Self {
+        ProofOfWork { retarget, miner }
+    }
+
+    pub fn from_config(config: &ChainConfig) -> Self {
+        Self::new(
+            config.retarget.clone(),
+            ParallelMiner::new(config.mining_threads),
+        )
+    }
+
+    // 父区块之后的下一个区块需要满足的目标值 (紧凑表示)
+    pub fn next_bits(&self, previous_blocks: &[Block]) -> u32 {
+        self.retarget.next_bits(previous_blocks)
+    }
+}
+
+impl ConsensusEngine for ProofOfWork {
+    fn prepare(&self, block: &mut Block, previous_blocks: &[Block]) -> Result<(), ChainError> {
+        block.bits = self.next_bits(previous_blocks);
+        block.hash = block.calculate_hash();
+        Ok(())
+    }
+
+    fn seal(
+        &self,
+        block: &mut Block,
+        _previous_blocks: &[Block],
+        _state: &ChainState,
+        cancel: &CancellationToken,
+        progress: Option<&mut dyn FnMut(MiningProgress)>,
+    ) -> Result<SealOutcome, ChainError> {
+        let (index, bits) = (block.index, block.bits);
+        let (nonce, stats) = self.miner.mine_with(block, bits, cancel, progress);
+        match nonce {
+            Some(_) => Ok(SealOutcome::Sealed(stats)),
+            None if cancel.is_cancelled() => Ok(SealOutcome::Cancelled(stats)),
+            None => Err(ChainError::InsufficientPow { index }),
+        }
+    }
+
+    // 每个区块的目标值由它之前的区块决定, 哈希必须满足该目标值
+    fn verify(
+        &self,
+        block: &Block,
+        previous_blocks: &[Block],
+        _state: &ChainState,
+    ) -> Result<(), ChainError> {
+        let bits = self.next_bits(previous_blocks);
+        if block.bits != bits {
+            return Err(ChainError::InvalidDifficulty {
+                index: block.index,
+                expected: bits,
+                actual: block.bits,
+            });
+        }
+        if !block.meets_target() {
+            return Err(ChainError::InsufficientPow { index: block.index });
+        }
+        Ok(())
+    }
+
+    // 目标值不能比 pow_limit 更容易, 哈希必须满足区块声明的目标值
+    fn check_header(&self, block: &Block) -> Result<(), ChainError> {
+        let limit = Target::from_compact(self.retarget.pow_limit).unwrap_or(Target::MAX);
+        match block.target() {
+            Some(target) if target.as_u256() <= limit.as_u256() => {}
+            _ => {
+                return Err(ChainError::InvalidDifficulty {
+                    index: block.index,
+                    expected: self.retarget.pow_limit,
+                    actual: block.bits,
+                })
+            }
+        }
+        if !block.meets_target() {
+            return Err(ChainError::InsufficientPow { index: block.index });
+        }
+        Ok(())
+    }
+
+    fn block_work(&self, block: &Block) -> U256 {
+        block.work()
+    }
+}
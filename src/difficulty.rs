@@ -0,0 +1,130 @@
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::block::Block;
+use crate::target::Target;
+
+// 难度调整参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetargetConfig {
+    // 期望的出块间隔 (秒)
+    pub target_block_time: i64,
+    // 每隔多少个区块调整一次难度, 小于 2 表示不调整
+    pub adjustment_window: u64,
+    // 单次调整目标值最多变化的倍数
+    pub max_adjustment_factor: u64,
+    // 允许的最容易目标值 (紧凑表示)
+    pub pow_limit: u32,
+}
+
+impl Default for RetargetConfig {
+    fn default() -> Self {
+        RetargetConfig {
+            target_block_time: 10,
+            adjustment_window: 10,
+            max_adjustment_factor: 4,
+            pow_limit: Target::MAX.to_compact(),
+        }
+    }
+}
+
+impl RetargetConfig {
+    // 计算下一个区块的目标值 (紧凑表示), previous_blocks 为从创世区块到父区块的链
+    //
+    // 每个调整周期结束时按实际耗时与期望耗时的比例缩放目标值, 比例限制在
+    // [1/max_adjustment_factor, max_adjustment_factor] 之间, 且不超过 pow_limit.
+    pub fn next_bits(&self, previous_blocks: &[Block]) -> u32 {
+        let Some(parent) = previous_blocks.last() else {
+            return self.pow_limit;
+        };
+        let height = parent.index + 1;
+        let window = self.adjustment_window;
+        if window < 2 || height % window != 0 || (previous_blocks.len() as u64) < window {
+            return parent.bits;
+        }
+        let Some(parent_target) = Target::from_compact(parent.bits) else {
+            return parent.bits;
+        };
+
+        let first = &previous_blocks[previous_blocks.len() - window as usize];
+        let expected = (self.target_block_time.max(1) * (window as i64 - 1)) as u64;
+        let factor = self.max_adjustment_factor.max(1);
+        let actual = (parent.timestamp - first.timestamp).max(0) as u64;
+
+        let current = parent_target.as_u256();
+        let scaled = if actual.saturating_mul(factor) <= expected {
+            current / factor
+        } else if actual >= expected.saturating_mul(factor) {
+            current.saturating_mul(U256::from(factor))
+        } else {
+            match current.checked_mul(U256::from(actual)) {
+                Some(product) => product / expected,
+                None => (current / expected).saturating_mul(U256::from(actual)),
+            }
+        };
+
+        let limit = Target::from_compact(self.pow_limit).unwrap_or(Target::MAX);
+        Target::from_u256(scaled.min(limit.as_u256())).to_compact()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(bits: u32, interval: i64, count: u64) -> Vec<Block> {
+        (0..count)
+            .map(|index| {
+                let mut block = Block::new(index, vec![], "0".repeat(64));
+                block.bits = bits;
+                block.timestamp = index as i64 * interval;
+                block
+            })
+            .collect()
+    }
+
+    fn target(bits: u32) -> U256 {
+        Target::from_compact(bits).unwrap().as_u256()
+    }
+
+    #[test]
+    fn test_difficulty_unchanged_inside_window() {
+        let config = RetargetConfig::default();
+        assert_eq!(config.next_bits(&blocks(0x1f00ffff, 0, 5)), 0x1f00ffff);
+    }
+
+    #[test]
+    fn test_difficulty_adjusts_smoothly() {
+        let config = RetargetConfig::default();
+        let bits = Target::from_leading_zeros(16).to_compact();
+
+        // 出块时间是期望的一半, 目标值减半 (难度翻倍)
+        let faster = config.next_bits(&blocks(bits, 5, 10));
+        assert_eq!(target(faster), target(bits) / 2);
+
+        // 出块时间是期望的 1.5 倍, 目标值同比放大
+        let slower = config.next_bits(&blocks(bits, 15, 10));
+        let expected = Target::from_u256(target(bits) * 3 / 2).to_compact();
+        assert_eq!(slower, expected);
+
+        // 接近目标间隔, 难度不变
+        assert_eq!(config.next_bits(&blocks(bits, 10, 10)), bits);
+    }
+
+    #[test]
+    fn test_difficulty_is_clamped() {
+        let config = RetargetConfig {
+            pow_limit: Target::from_leading_zeros(8).to_compact(),
+            ..RetargetConfig::default()
+        };
+        let bits = Target::from_leading_zeros(16).to_compact();
+
+        // 单次最多调整 4 倍
+        let faster = config.next_bits(&blocks(bits, 0, 10));
+        assert_eq!(target(faster), target(bits) / 4);
+
+        // 不会比 pow_limit 更容易
+        let limit = config.pow_limit;
+        assert_eq!(config.next_bits(&blocks(limit, 1000, 10)), limit);
+    }
+}
@@ -0,0 +1,109 @@
+use thiserror::Error;
+
+use crate::amount::Amount;
+use crate::config::LedgerMode;
+
+// 区块链错误类型
+#[derive(Debug, Error)]
+pub enum ChainError {
+    #[error("blockchain has no blocks")]
+    EmptyChain,
+    #[error("block {index} has an invalid hash")]
+    InvalidHash { index: u64 },
+    #[error("block {index} has a merkle root that does not match its transactions")]
+    InvalidMerkleRoot { index: u64 },
+    #[error("block {index} does not link to its predecessor")]
+    BrokenLink { index: u64 },
+    #[error("block {index} forks too far below the chain tip")]
+    ForkTooDeep { index: u64 },
+    #[error("block {hash} is already known")]
+    DuplicateBlock { hash: String },
+    #[error("block {index} timestamp is earlier than the median of recent blocks")]
+    TimestampTooOld { index: u64 },
+    #[error("block {index} timestamp is too far in the future")]
+    TimestampTooFarInFuture { index: u64 },
+    #[error("block {index} declares bits {actual:#010x}, expected {expected:#010x}")]
+    InvalidDifficulty {
+        index: u64,
+        expected: u32,
+        actual: u32,
+    },
+    #[error("block {index} does not meet the proof-of-work target")]
+    InsufficientPow { index: u64 },
+    #[error("block {index} is not signed by its scheduled validator")]
+    InvalidBlockSignature { index: u64 },
+    #[error("this node is not the scheduled producer of block {index}")]
+    NotBlockProducer { index: u64 },
+    #[error("block {index} exceeds the transaction count or size limit")]
+    BlockTooLarge { index: u64 },
+    #[error("block {index} contains more than one coinbase transaction")]
+    MultipleCoinbase { index: u64 },
+    #[error("block {index} coinbase pays {actual}, at most {max} allowed")]
+    InvalidCoinbaseAmount {
+        index: u64,
+        max: Amount,
+        actual: Amount,
+    },
+    #[error("orphan block pool is full")]
+    OrphanPoolFull,
+    #[error("cached chain state does not match the chain")]
+    InconsistentState,
+    #[error("transaction is not signed")]
+    MissingSignature,
+    #[error("transaction signature is invalid")]
+    InvalidSignature,
+    #[error("coinbase transaction is not allowed here")]
+    UnexpectedCoinbase,
+    #[error("transaction from {address} has nonce {actual}, expected {expected}")]
+    InvalidNonce {
+        address: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("transaction pool already has nonce {nonce} from {address}")]
+    DuplicateNonce { address: String, nonce: u64 },
+    #[error("transaction {txid} is already in the transaction pool")]
+    DuplicateTransaction { txid: String },
+    #[error("replacement transaction pays fee {actual}, at least {required} required")]
+    ReplacementFeeTooLow { required: Amount, actual: Amount },
+    #[error("transaction pool is full and the fee rate is too low")]
+    MempoolFull,
+    #[error("{address} already has {limit} transactions in the transaction pool")]
+    SenderLimitExceeded { address: String, limit: usize },
+    #[error("insufficient balance for {address}: available {available}, required {required}")]
+    Overspend {
+        address: String,
+        available: Amount,
+        required: Amount,
+    },
+    #[error("insufficient stake for {address}: available {available}, required {required}")]
+    InsufficientStake {
+        address: String,
+        available: Amount,
+        required: Amount,
+    },
+    #[error("block {index} is not in a later slot than its parent")]
+    InvalidSlot { index: u64 },
+    #[error("block {index} is in a slot that has not started yet")]
+    FutureSlot { index: u64 },
+    #[error("output {outpoint} does not exist or is already spent")]
+    UnknownOutput { outpoint: String },
+    #[error("output {outpoint} is spent more than once")]
+    DoubleSpend { outpoint: String },
+    #[error("output {outpoint} does not belong to the sender")]
+    InputNotOwned { outpoint: String },
+    #[error("outputs total {outputs} exceeds inputs total {inputs}")]
+    OutputsExceedInputs { inputs: Amount, outputs: Amount },
+    #[error("outputs total {outputs} does not match transaction amount {amount}")]
+    OutputsMismatch { amount: Amount, outputs: Amount },
+    #[error("output {outpoint} already exists")]
+    DuplicateOutput { outpoint: String },
+    #[error("transaction is not supported by the {mode:?} ledger")]
+    LedgerMismatch { mode: LedgerMode },
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("amount overflow")]
+    AmountOverflow,
+    #[error("storage error: {0}")]
+    Storage(#[from] std::io::Error),
+}
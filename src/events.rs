@@ -0,0 +1,28 @@
+// 区块链事件, 通过 Blockchain::subscribe 订阅
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent {
+    // 区块加入主链
+    BlockConnected {
+        hash: String,
+        index: u64,
+    },
+    // 链重组时区块离开主链
+    BlockDisconnected {
+        hash: String,
+        index: u64,
+    },
+    // 主链切换到更重的分支, depth 为断开的区块数
+    Reorganized {
+        old_tip: String,
+        new_tip: String,
+        fork_point: String,
+        depth: u64,
+    },
+    // 交易池中的交易被同一 sender 相同序号、手续费更高的交易替换
+    TransactionReplaced {
+        sender: String,
+        nonce: u64,
+        old_txid: String,
+        new_txid: String,
+    },
+}
@@ -0,0 +1,105 @@
+use sha2::{Digest, Sha256};
+
+// 当前区块头版本
+pub const HEADER_VERSION: u32 = 1;
+
+// 区块头的二进制布局 (整数均为小端序):
+// version(4) | index(8) | previous_hash(32) | merkle_root(32) | timestamp(8) | bits(4) | nonce(8)
+pub const HEADER_SIZE: usize = 96;
+// nonce 位于最后, 之前的部分在挖矿时保持不变
+const NONCE_OFFSET: usize = HEADER_SIZE - 8;
+
+// 固定长度的区块头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub version: u32,
+    pub index: u64,
+    pub previous_hash: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub timestamp: i64,
+    pub bits: u32,
+    pub nonce: u64,
+}
+
+impl BlockHeader {
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..12].copy_from_slice(&self.index.to_le_bytes());
+        bytes[12..44].copy_from_slice(&self.previous_hash);
+        bytes[44..76].copy_from_slice(&self.merkle_root);
+        bytes[76..84].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[84..88].copy_from_slice(&self.bits.to_le_bytes());
+        bytes[NONCE_OFFSET..].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; HEADER_SIZE]) -> Self {
+        BlockHeader {
+            version: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            index: u64::from_le_bytes(bytes[4..12].try_into().unwrap()),
+            previous_hash: bytes[12..44].try_into().unwrap(),
+            merkle_root: bytes[44..76].try_into().unwrap(),
+            timestamp: i64::from_le_bytes(bytes[76..84].try_into().unwrap()),
+            bits: u32::from_le_bytes(bytes[84..88].try_into().unwrap()),
+            nonce: u64::from_le_bytes(bytes[NONCE_OFFSET..].try_into().unwrap()),
+        }
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+}
+
+// 预先把 nonce 之前的字段喂给哈希器, 每次尝试只需再哈希 8 字节的 nonce
+#[derive(Debug, Clone)]
+pub struct HeaderHasher {
+    prefix: Sha256,
+}
+
+impl HeaderHasher {
+    pub fn new(header: &BlockHeader) -> Self {
+        let mut prefix = Sha256::new();
+        prefix.update(&header.to_bytes()[..NONCE_OFFSET]);
+        HeaderHasher { prefix }
+    }
+
+    pub fn hash_with_nonce(&self, nonce: u64) -> [u8; 32] {
+        let mut hasher = self.prefix.clone();
+        hasher.update(nonce.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> BlockHeader {
+        BlockHeader {
+            version: HEADER_VERSION,
+            index: 7,
+            previous_hash: [1; 32],
+            merkle_root: [2; 32],
+            timestamp: 1_700_000_000,
+            bits: 0x1f00ffff,
+            nonce: 42,
+        }
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = header();
+        assert_eq!(BlockHeader::from_bytes(&header.to_bytes()), header);
+    }
+
+    #[test]
+    fn test_prefix_hasher_matches_full_hash() {
+        let mut header = header();
+        let hasher = HeaderHasher::new(&header);
+        for nonce in [0, 1, 42, u64::MAX] {
+            header.nonce = nonce;
+            assert_eq!(hasher.hash_with_nonce(nonce), header.hash());
+        }
+    }
+}
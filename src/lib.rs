@@ -0,0 +1,42 @@
+pub mod amount;
+pub mod block;
+pub mod chain;
+pub mod config;
+pub mod consensus;
+pub mod difficulty;
+pub mod error;
+pub mod events;
+pub mod header;
+pub mod mempool;
+pub mod merkle;
+pub mod mining;
+pub mod stake;
+pub mod state;
+pub mod storage;
+pub mod target;
+pub mod tree;
+pub mod tx;
+pub mod utxo;
+pub mod wallet;
+
+pub use amount::Amount;
+pub use block::Block;
+pub use chain::{BlockStatus, Blockchain, ChainSnapshot};
+pub use config::{ChainConfig, LedgerMode};
+pub use consensus::{ConsensusEngine, ProofOfAuthority, ProofOfStake, ProofOfWork, SealOutcome};
+pub use difficulty::RetargetConfig;
+pub use error::ChainError;
+pub use events::ChainEvent;
+pub use header::{BlockHeader, HeaderHasher};
+pub use mempool::{Mempool, MempoolConfig};
+pub use merkle::MerkleProof;
+pub use mining::{CancellationToken, MiningOutcome, MiningProgress, MiningStats, ParallelMiner};
+pub use primitive_types::U256;
+pub use stake::StakeTable;
+pub use state::{AccountState, ChainState};
+pub use storage::{BlockStore, FileStore, MemoryStore};
+pub use target::Target;
+pub use tree::BlockTree;
+pub use tx::{Transaction, TxKind};
+pub use utxo::{OutPoint, TxOutput, UtxoSet};
+pub use wallet::Wallet;
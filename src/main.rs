@@ -1,247 +1,108 @@
-// Cargo.toml 依赖:
-// [dependencies]
-// sha2 = "0.10.7"
-// chrono = "0.4"
-// serde = { version = "1.0", features = ["derive"] }
-// serde_json = "1.0"
-// hex = "0.4"
-
-use sha2::{Sha256, Digest};
-use chrono::Utc;
-use serde::{Serialize, Deserialize};
-use std::fmt::Write;
-
-// 交易结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Transaction {
-    sender: String,
-    recipient: String,
-    amount: f64,
-    timestamp: i64,
-}
-
-impl Transaction {
-    fn new(sender: String, recipient: String, amount: f64) -> Self {
-        Transaction {
-            sender,
-            recipient,
-            amount,
-            timestamp: Utc::now().timestamp(),
-        }
-    }
-}
-
-// 区块结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Block {
-    index: u64,
-    timestamp: i64,
-    transactions: Vec<Transaction>,
-    previous_hash: String,
-    hash: String,
-    nonce: u64,
-}
-
-impl Block {
-    fn new(index: u64, transactions: Vec<Transaction>, previous_hash: String) -> Self {
-        let mut block = Block {
-            index,
-            timestamp: Utc::now().timestamp(),
-            transactions,
-            previous_hash,
-            hash: String::new(),
-            nonce: 0,
-        };
-        block.hash = block.calculate_hash();
-        block
-    }
-
-    fn calculate_hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        let data = format!(
-            "{}{}{}{}{}",
-            self.index,
-            self.timestamp,
-            serde_json::to_string(&self.transactions).unwrap(),
-            self.previous_hash,
-            self.nonce
-        );
-        hasher.update(data.as_bytes());
-        let result = hasher.finalize();
-        let mut hash = String::new();
-        for byte in result {
-            write!(&mut hash, "{:02x}", byte).expect("Unable to write hash");
-        }
-        hash
-    }
-
-    fn mine_block(&mut self, difficulty: usize) {
-        let target = "0".repeat(difficulty);
-        while &self.hash[..difficulty] != target.as_str() {
-            self.nonce += 1;
-            self.hash = self.calculate_hash();
-        }
-        println!("Block mined: {}", self.hash);
-    }
-}
-
-// 区块链结构
-#[derive(Debug, Serialize, Deserialize)]
-struct Blockchain {
-    chain: Vec<Block>,
-    pending_transactions: Vec<Transaction>,
-    difficulty: usize,
-    mining_reward: f64,
-}
-
-impl Blockchain {
-    fn new(difficulty: usize, mining_reward: f64) -> Self {
-        let mut chain = vec![];
-        // 创建创世区块
-        let genesis_block = Block::new(
-            0,
-            vec![],
-            "0".repeat(64),
-        );
-        chain.push(genesis_block);
-
-        Blockchain {
-            chain,
-            pending_transactions: vec![],
-            difficulty,
-            mining_reward,
-        }
-    }
-
-    fn get_latest_block(&self) -> &Block {
-        self.chain.last().unwrap()
-    }
-
-    fn mine_pending_transactions(&mut self, miner_address: String) {
-        // 创建挖矿奖励交易
-        let reward_tx = Transaction::new(
-            "System".to_string(),
-            miner_address,
-            self.mining_reward,
-        );
-        self.pending_transactions.push(reward_tx);
-
-        // 创建新区块
-        let mut block = Block::new(
-            self.chain.len() as u64,
-            self.pending_transactions.clone(),
-            self.get_latest_block().hash.clone(),
-        );
-
-        // 挖矿
-        block.mine_block(self.difficulty);
-
-        // 将区块添加到链中
-        println!("Block successfully mined!");
-        self.chain.push(block);
-
-        // 清空待处理交易池
-        self.pending_transactions = vec![];
-    }
-
-    fn add_transaction(&mut self, sender: String, recipient: String, amount: f64) {
-        let transaction = Transaction::new(sender, recipient, amount);
-        self.pending_transactions.push(transaction);
-    }
-
-    fn get_balance(&self, address: &str) -> f64 {
-        let mut balance = 0.0;
-
-        for block in &self.chain {
-            for transaction in &block.transactions {
-                if transaction.sender == address {
-                    balance -= transaction.amount;
-                }
-                if transaction.recipient == address {
-                    balance += transaction.amount;
-                }
-            }
-        }
-
-        balance
-    }
-
-    fn is_chain_valid(&self) -> bool {
-        for i in 1..self.chain.len() {
-            let current_block = &self.chain[i];
-            let previous_block = &self.chain[i - 1];
-
-            // 验证当前区块的哈希是否正确
-            if current_block.hash != current_block.calculate_hash() {
-                println!("Current hash is invalid");
-                return false;
-            }
-
-            // 验证区块链接是否正确
-            if current_block.previous_hash != previous_block.hash {
-                println!("Chain link is broken");
-                return false;
-            }
-        }
-        true
-    }
-}
+use blockchain::{
+    Amount, Blockchain, CancellationToken, ChainConfig, FileStore, MiningOutcome, MiningProgress,
+    Target, Wallet,
+};
 
 // 示例用法
 fn main() {
-    // 创建新的区块链，难度为4，挖矿奖励为100
-    let mut blockchain = Blockchain::new(4, 100.0);
-
-    println!("开始挖矿...");
-    blockchain.mine_pending_transactions("miner1".to_string());
-
-    // 添加一些交易
-    blockchain.add_transaction("address1".to_string(), "address2".to_string(), 50.0);
-    blockchain.add_transaction("address2".to_string(), "address3".to_string(), 30.0);
+    // 创建新的区块链，哈希需要16个前导零比特，挖矿奖励为100
+    let bits = Target::from_leading_zeros(16).to_compact();
+    // 传入数据目录时从磁盘恢复并持久化每个新区块
+    let mut blockchain = match std::env::args().nth(1) {
+        Some(dir) => {
+            let store = FileStore::open(&dir).expect("无法打开数据目录");
+            Blockchain::open(Box::new(store), ChainConfig::new(bits, coins(100)))
+                .expect("无法加载区块链")
+        }
+        None => Blockchain::new(bits, coins(100)),
+    };
+    println!("当前区块高度: {}", blockchain.chain().len() - 1);
+
+    let miner1 = Wallet::generate();
+    let address1 = Wallet::generate();
+    let address2 = Wallet::generate();
+    let address3 = Wallet::generate();
+
+    mine(&mut blockchain, &miner1);
+
+    // 矿工把奖励转给其他账户, 金额也可以从字符串解析
+    let half_coin: Amount = "0.5".parse().expect("金额格式错误");
+    blockchain
+        .add_transaction(miner1.transfer(address3.address(), half_coin, 0))
+        .expect("交易被拒绝");
+    blockchain
+        .add_transaction(miner1.transfer(address1.address(), coins(50), 1))
+        .expect("交易被拒绝");
+    blockchain
+        .add_transaction(miner1.transfer(address2.address(), coins(30), 2))
+        .expect("交易被拒绝");
+
+    mine(&mut blockchain, &miner1);
+
+    // address1 只能花费已确认的余额
+    blockchain
+        .add_transaction(address1.transfer(address3.address(), coins(20), 0))
+        .expect("交易被拒绝");
+    if let Err(err) =
+        blockchain.add_transaction(address2.transfer(address3.address(), coins(100), 0))
+    {
+        println!("交易被拒绝: {}", err);
+    }
 
-    println!("开始挖矿...");
-    blockchain.mine_pending_transactions("miner1".to_string());
+    mine(&mut blockchain, &miner1);
 
     // 查看余额
-    println!("Miner1的余额是: {}", blockchain.get_balance("miner1"));
-    println!("Address1的余额是: {}", blockchain.get_balance("address1"));
-    println!("Address2的余额是: {}", blockchain.get_balance("address2"));
-    println!("Address3的余额是: {}", blockchain.get_balance("address3"));
+    println!(
+        "Miner1的余额是: {}",
+        blockchain.get_balance(&miner1.address())
+    );
+    println!(
+        "Address1的余额是: {}",
+        blockchain.get_balance(&address1.address())
+    );
+    println!(
+        "Address2的余额是: {}",
+        blockchain.get_balance(&address2.address())
+    );
+    println!(
+        "Address3的余额是: {}",
+        blockchain.get_balance(&address3.address())
+    );
+
+    println!("累计工作量: {}", blockchain.chain_work());
 
     // 验证区块链
-    println!("区块链是否有效: {}", blockchain.is_chain_valid());
+    match blockchain.is_chain_valid() {
+        Ok(()) => println!("区块链是否有效: true"),
+        Err(err) => println!("区块链无效: {}", err),
+    }
 
     // 将区块链序列化为JSON（用于持久化或网络传输）
     let blockchain_json = serde_json::to_string_pretty(&blockchain).unwrap();
     println!("区块链JSON:\n{}", blockchain_json);
 }
 
-// 单元测试
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_blockchain_creation() {
-        let blockchain = Blockchain::new(4, 100.0);
-        assert_eq!(blockchain.chain.len(), 1); // 验证创世区块
-        assert_eq!(blockchain.difficulty, 4);
-        assert_eq!(blockchain.mining_reward, 100.0);
-    }
-
-    #[test]
-    fn test_mining() {
-        let mut blockchain = Blockchain::new(2, 100.0);
-        blockchain.add_transaction("sender".to_string(), "recipient".to_string(), 50.0);
-        blockchain.mine_pending_transactions("miner".to_string());
-        assert_eq!(blockchain.chain.len(), 2);
+fn mine(blockchain: &mut Blockchain, miner: &Wallet) {
+    println!("开始挖矿...");
+    let mut on_progress = |progress: MiningProgress| {
+        println!("已尝试 {} 个 nonce", progress.nonces_tried);
+    };
+    let cancel = CancellationToken::new();
+    match blockchain.mine_pending_transactions_with(
+        miner.address(),
+        &cancel,
+        Some(&mut on_progress),
+    ) {
+        Ok(MiningOutcome::Found { block, stats }) => println!(
+            "Block mined: {} ({:.0} H/s)",
+            block.hash(),
+            stats.hashrate()
+        ),
+        Ok(MiningOutcome::Cancelled { .. }) => println!("挖矿已取消"),
+        Err(err) => println!("挖矿失败: {}", err),
     }
+}
 
-    #[test]
-    fn test_chain_validity() {
-        let mut blockchain = Blockchain::new(2, 100.0);
-        blockchain.add_transaction("sender".to_string(), "recipient".to_string(), 50.0);
-        blockchain.mine_pending_transactions("miner".to_string());
-        assert!(blockchain.is_chain_valid());
-    }
-}
\ No newline at end of file
+fn coins(n: u64) -> Amount {
+    Amount::from_coins(n).expect("金额溢出")
+}
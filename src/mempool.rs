@@ -0,0 +1,516 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::amount::Amount;
+use crate::block::Block;
+use crate::error::ChainError;
+use crate::tx::Transaction;
+
+// 交易池参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    // 交易池中所有交易编码后的总字节数上限, 超出时驱逐手续费率最低的交易
+    pub max_size_bytes: usize,
+    // 每个 sender 在交易池中最多保存的交易数
+    pub max_per_sender: usize,
+    // 交易在交易池中最长保存的秒数
+    pub expiry_secs: i64,
+    // 替换交易时手续费至少需要增加的金额
+    pub min_fee_increment: Amount,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            max_size_bytes: 5_000_000,
+            max_per_sender: 25,
+            expiry_secs: 14 * 24 * 60 * 60,
+            min_fee_increment: Amount::from_base_units(1000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MempoolEntry {
+    transaction: Transaction,
+    size: usize,
+    // 进入交易池的时间
+    added_at: i64,
+    // 到达顺序, 手续费率相同时先到达的优先打包
+    sequence: u64,
+}
+
+// 等待打包的交易, 按 txid 去重, 按 sender 和序号索引
+// 只负责容量管理, 签名、序号和余额的校验由 Blockchain 完成
+#[derive(Debug, Clone, Serialize)]
+pub struct Mempool {
+    config: MempoolConfig,
+    entries: HashMap<String, MempoolEntry>,
+    // sender -> 序号 -> txid
+    #[serde(skip)]
+    by_sender: HashMap<String, BTreeMap<u64, String>>,
+    size_bytes: usize,
+    next_sequence: u64,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Mempool {
+            config,
+            entries: HashMap::new(),
+            by_sender: HashMap::new(),
+            size_bytes: 0,
+            next_sequence: 0,
+        }
+    }
+
+    pub fn config(&self) -> &MempoolConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // 所有交易编码后的总字节数
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    pub fn contains(&self, txid: &str) -> bool {
+        self.entries.contains_key(txid)
+    }
+
+    pub fn get(&self, txid: &str) -> Option<&Transaction> {
+        self.entries.get(txid).map(|entry| &entry.transaction)
+    }
+
+    // sender 使用该序号的交易
+    pub fn get_by_nonce(&self, sender: &str, nonce: u64) -> Option<&Transaction> {
+        let txid = self.by_sender.get(sender)?.get(&nonce)?;
+        self.get(txid)
+    }
+
+    // 按到达顺序排列的所有交易
+    pub fn transactions(&self) -> Vec<&Transaction> {
+        let mut entries: Vec<&MempoolEntry> = self.entries.values().collect();
+        entries.sort_by_key(|entry| entry.sequence);
+        entries
+            .into_iter()
+            .map(|entry| &entry.transaction)
+            .collect()
+    }
+
+    // sender 的所有交易, 按序号排列
+    pub fn from_sender<'a>(&'a self, sender: &str) -> impl Iterator<Item = &'a Transaction> + 'a {
+        self.by_sender
+            .get(sender)
+            .into_iter()
+            .flat_map(|nonces| nonces.values())
+            .filter_map(|txid| self.get(txid))
+    }
+
+    // 加入交易, 超出容量时驱逐手续费率最低的交易并返回被驱逐的交易
+    // 新交易自己的手续费率最低时不会被加入, 已被占用的序号只能通过 replace 替换
+    pub fn insert(
+        &mut self,
+        transaction: Transaction,
+        now: i64,
+    ) -> Result<Vec<Transaction>, ChainError> {
+        let txid = transaction.hash();
+        if self.entries.contains_key(&txid) {
+            return Err(ChainError::DuplicateTransaction { txid });
+        }
+        if self
+            .get_by_nonce(transaction.sender(), transaction.nonce())
+            .is_some()
+        {
+            return Err(ChainError::DuplicateNonce {
+                address: transaction.sender().to_string(),
+                nonce: transaction.nonce(),
+            });
+        }
+        let sender_count = self
+            .by_sender
+            .get(transaction.sender())
+            .map_or(0, BTreeMap::len);
+        if sender_count >= self.config.max_per_sender {
+            return Err(ChainError::SenderLimitExceeded {
+                address: transaction.sender().to_string(),
+                limit: self.config.max_per_sender,
+            });
+        }
+        let size = transaction.size();
+        if size > self.config.max_size_bytes {
+            return Err(ChainError::MempoolFull);
+        }
+
+        // 先选出需要驱逐的交易, 确认新交易能留下后再修改交易池
+        // 同一 sender 序号更小的交易是新交易的前置交易, 驱逐它会让新交易永远无法打包
+        let is_ancestor = |entry: &MempoolEntry| {
+            entry.transaction.sender() == transaction.sender()
+                && entry.transaction.nonce() < transaction.nonce()
+        };
+        let mut evicted = HashSet::new();
+        let mut freed = 0;
+        while self.size_bytes - freed + size > self.config.max_size_bytes {
+            let victim = self
+                .entries
+                .iter()
+                .filter(|(txid, entry)| !evicted.contains(*txid) && !is_ancestor(entry))
+                .min_by(|(_, a), (_, b)| {
+                    compare_fee_rate(&a.transaction, a.size, &b.transaction, b.size)
+                        .then(b.sequence.cmp(&a.sequence))
+                });
+            let Some((victim_txid, victim)) = victim else {
+                return Err(ChainError::MempoolFull);
+            };
+            if compare_fee_rate(&transaction, size, &victim.transaction, victim.size)
+                != Ordering::Greater
+            {
+                return Err(ChainError::MempoolFull);
+            }
+            freed += victim.size;
+            evicted.insert(victim_txid.clone());
+        }
+
+        let mut removed = Vec::new();
+        for txid in evicted {
+            removed.extend(self.remove_with_descendants(&txid));
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.insert_entry(
+            txid,
+            MempoolEntry {
+                transaction,
+                size,
+                added_at: now,
+                sequence,
+            },
+        );
+        Ok(removed)
+    }
+
+    // 用同一 sender 相同序号的交易替换池中的交易, 手续费至少增加 min_fee_increment
+    // 没有相同序号的交易时直接加入; 新交易无法进入交易池时保留原交易
+    // 成功时返回被替换的交易
+    pub fn replace(
+        &mut self,
+        transaction: Transaction,
+        now: i64,
+    ) -> Result<Option<Transaction>, ChainError> {
+        let Some(original) = self.get_by_nonce(transaction.sender(), transaction.nonce()) else {
+            self.insert(transaction, now)?;
+            return Ok(None);
+        };
+        let required = original
+            .fee()
+            .checked_add(self.config.min_fee_increment)
+            .ok_or(ChainError::AmountOverflow)?;
+        if transaction.fee() < required {
+            return Err(ChainError::ReplacementFeeTooLow {
+                required,
+                actual: transaction.fee(),
+            });
+        }
+
+        let txid = original.hash();
+        let entry = self.entries[&txid].clone();
+        self.remove(&txid);
+        match self.insert(transaction, now) {
+            Ok(_) => Ok(Some(entry.transaction)),
+            Err(err) => {
+                self.insert_entry(txid, entry);
+                Err(err)
+            }
+        }
+    }
+
+    fn insert_entry(&mut self, txid: String, entry: MempoolEntry) {
+        self.by_sender
+            .entry(entry.transaction.sender().to_string())
+            .or_default()
+            .insert(entry.transaction.nonce(), txid.clone());
+        self.size_bytes += entry.size;
+        self.entries.insert(txid, entry);
+    }
+
+    pub fn remove(&mut self, txid: &str) -> Option<Transaction> {
+        let entry = self.entries.remove(txid)?;
+        self.size_bytes -= entry.size;
+        let sender = entry.transaction.sender();
+        if let Some(nonces) = self.by_sender.get_mut(sender) {
+            nonces.remove(&entry.transaction.nonce());
+            if nonces.is_empty() {
+                self.by_sender.remove(sender);
+            }
+        }
+        Some(entry.transaction)
+    }
+
+    // 交易池中有交易的所有 sender
+    pub fn senders(&self) -> impl Iterator<Item = &str> {
+        self.by_sender.keys().map(String::as_str)
+    }
+
+    // 移除交易以及同一 sender 序号更大的交易, 后者缺少前序交易无法打包
+    pub fn remove_with_descendants(&mut self, txid: &str) -> Vec<Transaction> {
+        let Some(transaction) = self.get(txid) else {
+            return Vec::new();
+        };
+        let descendants: Vec<String> = self
+            .by_sender
+            .get(transaction.sender())
+            .map(|nonces| {
+                nonces
+                    .range(transaction.nonce()..)
+                    .map(|(_, txid)| txid.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        descendants
+            .iter()
+            .filter_map(|txid| self.remove(txid))
+            .collect()
+    }
+
+    // 只移除区块中实际打包的交易
+    pub fn remove_included(&mut self, block: &Block) -> Vec<Transaction> {
+        block
+            .transactions
+            .iter()
+            .filter_map(|transaction| self.remove(&transaction.hash()))
+            .collect()
+    }
+
+    // 移除不满足条件的交易, 返回被移除的交易
+    pub fn retain(&mut self, keep: impl Fn(&Transaction) -> bool) -> Vec<Transaction> {
+        let stale: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| !keep(&entry.transaction))
+            .map(|(txid, _)| txid.clone())
+            .collect();
+        stale.iter().filter_map(|txid| self.remove(txid)).collect()
+    }
+
+    // 移除在交易池中停留超过 expiry_secs 的交易
+    pub fn expire(&mut self, now: i64) -> Vec<Transaction> {
+        let cutoff = now.saturating_sub(self.config.expiry_secs);
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.added_at < cutoff)
+            .map(|(txid, _)| txid.clone())
+            .collect();
+        expired
+            .iter()
+            .filter_map(|txid| self.remove(txid))
+            .collect()
+    }
+
+    // 按到达顺序取出所有交易并清空交易池
+    pub fn drain(&mut self) -> Vec<Transaction> {
+        let transactions = self.transactions().into_iter().cloned().collect();
+        self.entries.clear();
+        self.by_sender.clear();
+        self.size_bytes = 0;
+        transactions
+    }
+
+    // 按手续费率从高到低选择要打包的交易, 直到达到交易数或大小上限
+    // 每个 sender 的交易必须从 confirmed_nonce 开始连续编号, 序号有空缺的交易留在交易池中
+    pub fn select(
+        &self,
+        confirmed_nonce: impl Fn(&str) -> u64,
+        max_count: usize,
+        max_size: usize,
+    ) -> Vec<Transaction> {
+        let mut queues: BTreeMap<&str, VecDeque<&MempoolEntry>> = BTreeMap::new();
+        for (sender, nonces) in &self.by_sender {
+            let first = confirmed_nonce(sender);
+            let ready = (first..)
+                .zip(nonces.range(first..))
+                .take_while(|(expected, (nonce, _))| *expected == **nonce)
+                .map(|(_, (_, txid))| &self.entries[txid]);
+            queues.insert(sender, ready.collect());
+        }
+
+        let mut selected = Vec::new();
+        let mut block_size = 0;
+        while selected.len() < max_count {
+            // 各 sender 队首交易中手续费率最高的, 相同时先到达的优先
+            let best = queues
+                .iter()
+                .filter_map(|(sender, queue)| queue.front().map(|head| (*sender, *head)))
+                .max_by(|(_, a), (_, b)| {
+                    compare_fee_rate(&a.transaction, a.size, &b.transaction, b.size)
+                        .then(b.sequence.cmp(&a.sequence))
+                });
+            let Some((sender, entry)) = best else {
+                break;
+            };
+            // 放不下的交易之后的同一 sender 交易也不能打包
+            if block_size + entry.size > max_size {
+                queues.remove(sender);
+                continue;
+            }
+            block_size += entry.size;
+            selected.push(entry.transaction.clone());
+            if let Some(queue) = queues.get_mut(sender) {
+                queue.pop_front();
+            }
+        }
+        selected
+    }
+}
+
+// 比较两笔交易每字节的手续费, 交叉相乘避免除法误差
+fn compare_fee_rate(a: &Transaction, a_size: usize, b: &Transaction, b_size: usize) -> Ordering {
+    let a_rate = a.fee().base_units() as u128 * b_size as u128;
+    let b_rate = b.fee().base_units() as u128 * a_size as u128;
+    a_rate.cmp(&b_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::wallet::Wallet;
+
+    fn coins(n: u64) -> Amount {
+        Amount::from_coins(n).unwrap()
+    }
+
+    fn transfer(wallet: &Wallet, fee: u64, nonce: u64) -> Transaction {
+        wallet.transfer_with_fee("recipient".to_string(), coins(1), coins(fee), nonce)
+    }
+
+    #[test]
+    fn test_rejects_duplicates_and_sender_limit() {
+        let mut mempool = Mempool::new(MempoolConfig {
+            max_per_sender: 2,
+            ..MempoolConfig::default()
+        });
+        let alice = Wallet::generate();
+        let first = transfer(&alice, 1, 0);
+        mempool.insert(first.clone(), 0).unwrap();
+        assert!(matches!(
+            mempool.insert(first, 0),
+            Err(ChainError::DuplicateTransaction { .. })
+        ));
+        assert!(matches!(
+            mempool.insert(transfer(&alice, 2, 0), 0),
+            Err(ChainError::DuplicateNonce { nonce: 0, .. })
+        ));
+        mempool.insert(transfer(&alice, 1, 1), 0).unwrap();
+        assert!(matches!(
+            mempool.insert(transfer(&alice, 1, 2), 0),
+            Err(ChainError::SenderLimitExceeded { limit: 2, .. })
+        ));
+        assert_eq!(mempool.len(), 2);
+    }
+
+    #[test]
+    fn test_evicts_lowest_fee_rate_when_full() {
+        let (alice, bob, carol) = (Wallet::generate(), Wallet::generate(), Wallet::generate());
+        let low = transfer(&alice, 1, 0);
+        let size = low.size();
+        let mut mempool = Mempool::new(MempoolConfig {
+            max_size_bytes: 2 * size,
+            ..MempoolConfig::default()
+        });
+        mempool.insert(low.clone(), 0).unwrap();
+        mempool.insert(transfer(&bob, 3, 0), 0).unwrap();
+
+        // 交易池已满, 手续费更高的交易驱逐手续费最低的交易
+        let evicted = mempool.insert(transfer(&carol, 2, 0), 0).unwrap();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].hash(), low.hash());
+        assert_eq!(mempool.size_bytes(), 2 * size);
+
+        // 手续费不高于池中最低手续费的交易不能进入
+        assert!(matches!(
+            mempool.insert(transfer(&alice, 2, 0), 0),
+            Err(ChainError::MempoolFull)
+        ));
+        assert_eq!(mempool.len(), 2);
+
+        // 不驱逐同一 sender 序号更小的交易, 而是驱逐其他 sender 的交易
+        let evicted = mempool.insert(transfer(&carol, 5, 1), 0).unwrap();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].sender(), bob.address());
+        assert_eq!(mempool.from_sender(&carol.address()).count(), 2);
+
+        // 池中只剩前置交易时拒绝新交易
+        assert!(matches!(
+            mempool.insert(transfer(&carol, 9, 2), 0),
+            Err(ChainError::MempoolFull)
+        ));
+        assert_eq!(mempool.len(), 2);
+    }
+
+    #[test]
+    fn test_expiry_and_removal_of_included_transactions() {
+        let mut mempool = Mempool::new(MempoolConfig {
+            expiry_secs: 50,
+            ..MempoolConfig::default()
+        });
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        let old = transfer(&alice, 1, 0);
+        let included = transfer(&bob, 1, 0);
+        let waiting = transfer(&bob, 1, 1);
+        mempool.insert(old.clone(), 0).unwrap();
+        mempool.insert(included.clone(), 100).unwrap();
+        mempool.insert(waiting.clone(), 100).unwrap();
+
+        let expired = mempool.expire(120);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].hash(), old.hash());
+
+        // 区块中的其他交易不影响交易池, 只移除实际打包的交易
+        let block = Block::new(1, vec![included.clone(), old], "0".repeat(64));
+        let removed = mempool.remove_included(&block);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].hash(), included.hash());
+        assert_eq!(mempool.len(), 1);
+        assert!(mempool.contains(&waiting.hash()));
+        assert_eq!(mempool.size_bytes(), waiting.size());
+    }
+
+    #[test]
+    fn test_select_waits_for_nonce_gaps() {
+        let mut mempool = Mempool::new(MempoolConfig::default());
+        let (alice, bob) = (Wallet::generate(), Wallet::generate());
+        mempool.insert(transfer(&alice, 1, 0), 0).unwrap();
+        mempool.insert(transfer(&alice, 5, 1), 0).unwrap();
+        mempool.insert(transfer(&bob, 9, 1), 0).unwrap();
+        mempool.insert(transfer(&bob, 2, 3), 0).unwrap();
+
+        // bob 已确认 1 笔交易, 序号 3 之前缺少序号 2
+        let confirmed = |sender: &str| u64::from(sender == bob.address());
+        let selected = mempool.select(confirmed, 10, usize::MAX);
+        let picked: Vec<(String, u64)> = selected
+            .iter()
+            .map(|tx| (tx.sender().to_string(), tx.nonce()))
+            .collect();
+        assert_eq!(
+            picked,
+            [
+                (bob.address(), 1),
+                (alice.address(), 0),
+                (alice.address(), 1)
+            ]
+        );
+
+        let selected = mempool.select(confirmed, 2, usize::MAX);
+        assert_eq!(selected.len(), 2);
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// 空区块的默克尔根
+pub const EMPTY_ROOT: [u8; 32] = [0u8; 32];
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+// 自底向上计算默克尔根, 奇数个节点时复制最后一个节点
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return EMPTY_ROOT;
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    level[0]
+}
+
+// 证明路径上的一个兄弟节点
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub sibling: String,
+    // 兄弟节点是否在左侧
+    pub is_left: bool,
+}
+
+// 交易包含证明: 从叶子到根的兄弟节点路径
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub tx_hash: String,
+    pub steps: Vec<ProofStep>,
+}
+
+impl MerkleProof {
+    // 为第 index 个叶子生成证明
+    pub fn generate(leaves: &[[u8; 32]], index: usize) -> Option<Self> {
+        let tx_hash = hex::encode(leaves.get(index)?);
+        let mut steps = Vec::new();
+        let mut level = leaves.to_vec();
+        let mut position = index;
+
+        while level.len() > 1 {
+            let sibling_position = position ^ 1;
+            let sibling = level.get(sibling_position).unwrap_or(&level[position]);
+            steps.push(ProofStep {
+                sibling: hex::encode(sibling),
+                is_left: sibling_position < position,
+            });
+            level = level
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            position /= 2;
+        }
+
+        Some(MerkleProof { tx_hash, steps })
+    }
+
+    // 用证明路径重新计算根并与给定的默克尔根比较
+    pub fn verify(&self, merkle_root: &str) -> bool {
+        let Some(mut current) = decode_hash(&self.tx_hash) else {
+            return false;
+        };
+        for step in &self.steps {
+            let Some(sibling) = decode_hash(&step.sibling) else {
+                return false;
+            };
+            current = if step.is_left {
+                hash_pair(&sibling, &current)
+            } else {
+                hash_pair(&current, &sibling)
+            };
+        }
+        hex::encode(current) == merkle_root
+    }
+}
+
+pub fn decode_hash(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| Sha256::digest([i]).into()).collect()
+    }
+
+    #[test]
+    fn test_proofs_verify_for_every_leaf() {
+        for n in 1..=7 {
+            let leaves = leaves(n);
+            let root = hex::encode(merkle_root(&leaves));
+            for index in 0..leaves.len() {
+                let proof = MerkleProof::generate(&leaves, index).unwrap();
+                assert!(proof.verify(&root), "leaf {} of {}", index, n);
+            }
+            assert!(MerkleProof::generate(&leaves, leaves.len()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_proof_rejects_wrong_leaf() {
+        let leaves = leaves(4);
+        let root = hex::encode(merkle_root(&leaves));
+        let mut proof = MerkleProof::generate(&leaves, 1).unwrap();
+        proof.tx_hash = hex::encode(leaves[2]);
+        assert!(!proof.verify(&root));
+    }
+}
@@ -0,0 +1,358 @@
+use chrono::Utc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::block::Block;
+use crate::header::HeaderHasher;
+use crate::merkle;
+use crate::target::Target;
+
+impl Block {
+    // 工作量证明: 记录目标值后不断递增 nonce 直到哈希满足要求
+    // 每轮只预先哈希一次区块头前缀, nonce 用尽时滚动额外随机数和时间戳后从 0 重新开始
+    pub fn mine_block(&mut self, bits: u32) {
+        self.bits = bits;
+        if let Some(target) = Target::from_compact(bits) {
+            'rounds: loop {
+                let hasher = HeaderHasher::new(&self.header());
+                let mut nonce = Some(self.nonce);
+                while let Some(current) = nonce {
+                    if target.is_met_by(&hasher.hash_with_nonce(current)) {
+                        self.nonce = current;
+                        break 'rounds;
+                    }
+                    nonce = current.checked_add(1);
+                }
+                if !self.roll_extra_nonce() {
+                    break;
+                }
+            }
+        }
+        self.hash = self.calculate_hash();
+    }
+
+    // 刷新时间戳并递增挖矿奖励交易的额外随机数, 重新计算默克尔根, nonce 归零
+    // 区块头没有任何变化时返回 false
+    pub fn roll_extra_nonce(&mut self) -> bool {
+        let timestamp = Utc::now().timestamp().max(self.timestamp);
+        let mut changed = timestamp != self.timestamp;
+        self.timestamp = timestamp;
+
+        let coinbase = self
+            .transactions
+            .iter_mut()
+            .rev()
+            .find(|transaction| transaction.is_coinbase());
+        if let Some(coinbase) = coinbase {
+            if let Some(extra_nonce) = coinbase.extra_nonce().checked_add(1) {
+                coinbase.set_extra_nonce(extra_nonce);
+                self.merkle_root = self.calculate_merkle_root();
+                changed = true;
+            }
+        }
+
+        self.nonce = 0;
+        self.hash = self.calculate_hash();
+        changed
+    }
+
+    // 哈希是否不大于区块声明的目标值
+    pub fn meets_target(&self) -> bool {
+        match (
+            Target::from_compact(self.bits),
+            merkle::decode_hash(&self.hash),
+        ) {
+            (Some(target), Some(hash)) => target.is_met_by(&hash),
+            _ => false,
+        }
+    }
+}
+
+// 一次挖矿的统计信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningStats {
+    pub hashes: u64,
+    pub elapsed: Duration,
+}
+
+impl MiningStats {
+    // 每秒计算的哈希次数
+    pub fn hashrate(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return self.hashes as f64;
+        }
+        self.hashes as f64 / seconds
+    }
+}
+
+// 取消令牌, 克隆后在任意线程调用 cancel 即可中止挖矿
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+// 挖矿进度, 通过回调定期报告
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningProgress {
+    pub nonces_tried: u64,
+    pub elapsed: Duration,
+}
+
+// 挖矿结果
+#[derive(Debug, Clone)]
+pub enum MiningOutcome {
+    Found { block: Block, stats: MiningStats },
+    Cancelled { stats: MiningStats },
+}
+
+// 工作线程每尝试这么多次 nonce 汇报一次计数
+const HASH_REPORT_BATCH: u64 = 1024;
+
+// 多线程矿工: 第 i 个线程尝试 nonce i, i + n, i + 2n, ...
+// 整个 nonce 范围都不满足时滚动额外随机数和时间戳, 再从 0 开始
+#[derive(Debug, Clone)]
+pub struct ParallelMiner {
+    threads: usize,
+    progress_interval: Duration,
+    max_nonce: u64,
+}
+
+impl ParallelMiner {
+    pub fn new(threads: usize) -> Self {
+        ParallelMiner {
+            threads: threads.max(1),
+            progress_interval: Duration::from_millis(100),
+            max_nonce: u64::MAX,
+        }
+    }
+
+    // 使用全部可用的 CPU 核心
+    pub fn with_available_parallelism() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+
+    // 设置进度回调的调用间隔
+    pub fn with_progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    // 限制每轮尝试的 nonce 上限, 超过后滚动额外随机数
+    pub fn with_max_nonce(mut self, max_nonce: u64) -> Self {
+        self.max_nonce = max_nonce;
+        self
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    // 为区块寻找满足 bits 的 nonce, 任一线程找到后其余线程立即停止
+    // 目标值无效或额外随机数也用尽时返回 None
+    pub fn mine(&self, block: &mut Block, bits: u32) -> (Option<u64>, MiningStats) {
+        self.mine_with(block, bits, &CancellationToken::new(), None)
+    }
+
+    // 可取消的挖矿, progress 在调用线程上按 progress_interval 定期被调用
+    // 被取消时返回 None
+    pub fn mine_with(
+        &self,
+        block: &mut Block,
+        bits: u32,
+        cancel: &CancellationToken,
+        mut progress: Option<&mut dyn FnMut(MiningProgress)>,
+    ) -> (Option<u64>, MiningStats) {
+        let started = Instant::now();
+        let hashes = AtomicU64::new(0);
+        block.bits = bits;
+        block.nonce = 0;
+
+        let mut nonce = None;
+        if let Some(target) = Target::from_compact(bits) {
+            loop {
+                let hasher = HeaderHasher::new(&block.header());
+                nonce = self.search(&hasher, target, cancel, &mut progress, &hashes, started);
+                if nonce.is_some() || cancel.is_cancelled() || !block.roll_extra_nonce() {
+                    break;
+                }
+            }
+        }
+
+        if let Some(nonce) = nonce {
+            block.nonce = nonce;
+        }
+        block.hash = block.calculate_hash();
+        let stats = MiningStats {
+            hashes: hashes.into_inner(),
+            elapsed: started.elapsed(),
+        };
+        (nonce, stats)
+    }
+
+    // 在 [0, max_nonce] 范围内并行搜索一轮
+    fn search(
+        &self,
+        hasher: &HeaderHasher,
+        target: Target,
+        cancel: &CancellationToken,
+        progress: &mut Option<&mut dyn FnMut(MiningProgress)>,
+        hashes: &AtomicU64,
+        started: Instant,
+    ) -> Option<u64> {
+        let found = AtomicBool::new(false);
+        let solution = Mutex::new(None);
+
+        thread::scope(|scope| {
+            let (done_tx, done_rx) = mpsc::channel();
+            for worker in 0..self.threads {
+                let (found, solution) = (&found, &solution);
+                let done_tx = done_tx.clone();
+                let step = self.threads as u64;
+                scope.spawn(move || {
+                    let mut nonce = Some(worker as u64);
+                    let mut attempts = 0;
+                    while let Some(current) = nonce.filter(|nonce| *nonce <= self.max_nonce) {
+                        if found.load(Ordering::Relaxed) || cancel.is_cancelled() {
+                            break;
+                        }
+                        attempts += 1;
+                        if attempts == HASH_REPORT_BATCH {
+                            hashes.fetch_add(attempts, Ordering::Relaxed);
+                            attempts = 0;
+                        }
+                        if target.is_met_by(&hasher.hash_with_nonce(current)) {
+                            if !found.swap(true, Ordering::AcqRel) {
+                                *solution.lock().unwrap() = Some(current);
+                            }
+                            break;
+                        }
+                        nonce = current.checked_add(step);
+                    }
+                    hashes.fetch_add(attempts, Ordering::Relaxed);
+                    let _ = done_tx.send(());
+                });
+            }
+            drop(done_tx);
+
+            // 等待所有工作线程结束, 期间定期报告进度
+            let mut running = self.threads;
+            while running > 0 {
+                match done_rx.recv_timeout(self.progress_interval) {
+                    Ok(()) => running -= 1,
+                    Err(RecvTimeoutError::Timeout) => {
+                        if let Some(callback) = progress.as_mut() {
+                            callback(MiningProgress {
+                                nonces_tried: hashes.load(Ordering::Relaxed),
+                                elapsed: started.elapsed(),
+                            });
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        solution.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::tx::Transaction;
+
+    #[test]
+    fn test_parallel_miner_finds_valid_nonce() {
+        let bits = Target::from_leading_zeros(12).to_compact();
+        let mut block = Block::new(1, vec![], "0".repeat(64));
+        let (nonce, stats) = ParallelMiner::new(4).mine(&mut block, bits);
+
+        assert_eq!(nonce, Some(block.nonce));
+        assert!(block.meets_target());
+        assert_eq!(block.hash, block.calculate_hash());
+        assert!(stats.hashes > 0);
+        assert!(stats.hashrate() > 0.0);
+    }
+
+    #[test]
+    fn test_mine_block_uses_header_prefix() {
+        let bits = Target::from_leading_zeros(12).to_compact();
+        let mut block = Block::new(1, vec![], "0".repeat(64));
+        block.mine_block(bits);
+
+        // 预先哈希前缀得到的结果与完整计算的区块哈希一致
+        assert!(block.meets_target());
+        assert_eq!(block.hash, block.calculate_hash());
+    }
+
+    #[test]
+    fn test_mining_can_be_cancelled_from_progress_callback() {
+        // 几乎不可能满足的目标值
+        let bits = Target::from_leading_zeros(200).to_compact();
+        let mut block = Block::new(1, vec![], "0".repeat(64));
+        let cancel = CancellationToken::new();
+        let mut reports = Vec::new();
+        let mut on_progress = |progress: MiningProgress| {
+            reports.push(progress);
+            cancel.cancel();
+        };
+
+        let miner = ParallelMiner::new(2).with_progress_interval(Duration::from_millis(10));
+        let (nonce, stats) = miner.mine_with(&mut block, bits, &cancel, Some(&mut on_progress));
+
+        assert_eq!(nonce, None);
+        assert!(!reports.is_empty());
+        assert!(stats.hashes >= reports[0].nonces_tried);
+    }
+
+    #[test]
+    fn test_miner_rolls_extra_nonce_when_nonce_range_exhausted() {
+        let bits = Target::from_leading_zeros(10).to_compact();
+        let coinbase = Transaction::coinbase("miner".to_string(), Amount::from_base_units(1));
+        let mut block = Block::new(1, vec![coinbase], "0".repeat(64));
+        let original_root = block.merkle_root.clone();
+
+        // 每轮只允许 8 个 nonce, 几乎必然需要滚动额外随机数
+        let miner = ParallelMiner::new(2).with_max_nonce(7);
+        let (nonce, stats) = miner.mine(&mut block, bits);
+
+        assert!(nonce.unwrap() <= 7);
+        assert!(block.meets_target());
+        assert!(block.transactions[0].extra_nonce() > 0);
+        assert_ne!(block.merkle_root, original_root);
+        assert_eq!(block.merkle_root, block.calculate_merkle_root());
+        assert!(stats.hashes > 8);
+    }
+
+    #[test]
+    fn test_roll_without_coinbase_only_refreshes_timestamp() {
+        let mut block = Block::new(1, vec![], "0".repeat(64));
+        block.timestamp = 0;
+        block.nonce = 5;
+        assert!(block.roll_extra_nonce());
+        assert!(block.timestamp > 0);
+        assert_eq!(block.nonce, 0);
+        // 同一秒内再次滚动没有任何变化
+        block.timestamp = i64::MAX;
+        assert!(!block.roll_extra_nonce());
+    }
+}
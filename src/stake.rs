@@ -0,0 +1,168 @@
+use primitive_types::U256;
+use std::collections::BTreeMap;
+
+use crate::amount::Amount;
+use crate::block::Block;
+use crate::error::ChainError;
+use crate::tx::{Transaction, TxKind};
+
+// 质押表: 地址到锁定金额, 按地址排序保证遍历顺序和提议者选择是确定的
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StakeTable {
+    stakes: BTreeMap<String, Amount>,
+}
+
+impl StakeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 按顺序重放区块中的质押交易
+    pub fn from_blocks(blocks: &[Block]) -> Result<Self, ChainError> {
+        let mut table = StakeTable::new();
+        for transaction in blocks.iter().flat_map(|block| block.transactions()) {
+            table.apply(transaction)?;
+        }
+        Ok(table)
+    }
+
+    // 应用一笔交易, 解除质押的金额不能超过已质押金额
+    pub fn apply(&mut self, transaction: &Transaction) -> Result<(), ChainError> {
+        if transaction.is_coinbase() {
+            return Ok(());
+        }
+        match transaction.kind() {
+            TxKind::Transfer => Ok(()),
+            TxKind::Stake => self.lock(transaction.sender(), transaction.amount()),
+            TxKind::Unstake => self.release(transaction.sender(), transaction.amount()),
+        }
+    }
+
+    // 撤销 apply 的效果, 断开区块时按交易的逆序调用
+    pub fn revert(&mut self, transaction: &Transaction) -> Result<(), ChainError> {
+        if transaction.is_coinbase() {
+            return Ok(());
+        }
+        match transaction.kind() {
+            TxKind::Transfer => Ok(()),
+            TxKind::Stake => self.release(transaction.sender(), transaction.amount()),
+            TxKind::Unstake => self.lock(transaction.sender(), transaction.amount()),
+        }
+    }
+
+    fn lock(&mut self, staker: &str, amount: Amount) -> Result<(), ChainError> {
+        let stake = self.stakes.entry(staker.to_string()).or_default();
+        *stake = stake
+            .checked_add(amount)
+            .ok_or(ChainError::AmountOverflow)?;
+        Ok(())
+    }
+
+    fn release(&mut self, staker: &str, amount: Amount) -> Result<(), ChainError> {
+        let available = self.stake_of(staker);
+        let remaining =
+            available
+                .checked_sub(amount)
+                .ok_or_else(|| ChainError::InsufficientStake {
+                    address: staker.to_string(),
+                    available,
+                    required: amount,
+                })?;
+        if remaining.is_zero() {
+            self.stakes.remove(staker);
+        } else {
+            self.stakes.insert(staker.to_string(), remaining);
+        }
+        Ok(())
+    }
+
+    pub fn stake_of(&self, address: &str) -> Amount {
+        self.stakes.get(address).copied().unwrap_or_default()
+    }
+
+    // 全部质押金额, 用 u128 累加避免溢出
+    pub fn total(&self) -> u128 {
+        self.stakes
+            .values()
+            .map(|stake| stake.base_units() as u128)
+            .sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Amount)> {
+        self.stakes
+            .iter()
+            .map(|(address, stake)| (address.as_str(), *stake))
+    }
+
+    // 按质押权重选出提议者: 把随机数对总质押取模, 落在哪个地址的区间就选谁
+    pub fn select_proposer(&self, randomness: &[u8; 32]) -> Option<&str> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let mut point = (U256::from_big_endian(randomness) % U256::from(total)).as_u128();
+        for (address, stake) in self.iter() {
+            let weight = stake.base_units() as u128;
+            if point < weight {
+                return Some(address);
+            }
+            point -= weight;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Wallet;
+
+    fn coins(n: u64) -> Amount {
+        Amount::from_coins(n).unwrap()
+    }
+
+    #[test]
+    fn test_stake_and_unstake() {
+        let wallet = Wallet::generate();
+        let mut table = StakeTable::new();
+        table.apply(&wallet.stake(coins(10), 0)).unwrap();
+        table.apply(&wallet.unstake(coins(4), 1)).unwrap();
+        assert_eq!(table.stake_of(&wallet.address()), coins(6));
+
+        assert!(matches!(
+            table.apply(&wallet.unstake(coins(7), 2)),
+            Err(ChainError::InsufficientStake { .. })
+        ));
+        table.apply(&wallet.unstake(coins(6), 2)).unwrap();
+        assert_eq!(table.total(), 0);
+        assert!(table.select_proposer(&[0; 32]).is_none());
+    }
+
+    #[test]
+    fn test_proposer_selection_is_stake_weighted() {
+        let mut table = StakeTable::new();
+        table
+            .apply(&Transaction::stake(
+                "a".to_string(),
+                Amount::from_base_units(1),
+            ))
+            .unwrap();
+        table
+            .apply(&Transaction::stake(
+                "b".to_string(),
+                Amount::from_base_units(3),
+            ))
+            .unwrap();
+
+        // 随机数取模后 0 落在 a 的区间, 1..4 落在 b 的区间
+        let randomness = |value: u8| {
+            let mut bytes = [0u8; 32];
+            bytes[31] = value;
+            bytes
+        };
+        let picks: Vec<_> = (0..8)
+            .map(|value| table.select_proposer(&randomness(value)).unwrap())
+            .collect();
+        assert_eq!(picks, ["a", "b", "b", "b", "a", "b", "b", "b"]);
+    }
+}
@@ -0,0 +1,602 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::amount::Amount;
+use crate::block::Block;
+use crate::config::LedgerMode;
+use crate::error::ChainError;
+use crate::stake::StakeTable;
+use crate::tx::{Transaction, TxKind};
+use crate::utxo::{OutPoint, TxOutput, UtxoSet};
+
+// 账户状态: 可用余额和已发送的交易数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub balance: Amount,
+    pub nonce: u64,
+}
+
+// 主链链尾处的账户状态和质押表, 随区块的连接和断开增量更新
+// UTXO 模式下还维护未花费输出集合, 账户余额为其名下未花费输出的总额
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainState {
+    ledger: LedgerMode,
+    // 只保存非默认状态的账户
+    accounts: HashMap<String, AccountState>,
+    stakes: StakeTable,
+    utxos: UtxoSet,
+    // 每个已连接区块花费的输出, 断开区块时恢复
+    spent_outputs: HashMap<String, Vec<(OutPoint, TxOutput)>>,
+}
+
+impl ChainState {
+    pub fn new(ledger: LedgerMode) -> Self {
+        ChainState {
+            ledger,
+            ..Self::default()
+        }
+    }
+
+    // 从创世区块开始按顺序连接所有区块
+    pub fn from_blocks(ledger: LedgerMode, blocks: &[Block]) -> Result<Self, ChainError> {
+        let mut state = ChainState::new(ledger);
+        for block in blocks {
+            state.connect_block(block)?;
+        }
+        Ok(state)
+    }
+
+    pub fn ledger(&self) -> LedgerMode {
+        self.ledger
+    }
+
+    pub fn utxos(&self) -> &UtxoSet {
+        &self.utxos
+    }
+
+    pub fn account(&self, address: &str) -> AccountState {
+        self.accounts.get(address).copied().unwrap_or_default()
+    }
+
+    pub fn balance(&self, address: &str) -> Amount {
+        self.account(address).balance
+    }
+
+    pub fn nonce(&self, address: &str) -> u64 {
+        self.account(address).nonce
+    }
+
+    pub fn stakes(&self) -> &StakeTable {
+        &self.stakes
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = (&str, AccountState)> {
+        self.accounts
+            .iter()
+            .map(|(address, account)| (address.as_str(), *account))
+    }
+
+    // 应用区块中的交易, 任何账户余额或质押不足时报错且状态保持不变
+    pub fn connect_block(&mut self, block: &Block) -> Result<(), ChainError> {
+        match self.ledger {
+            LedgerMode::Account => self.connect_account_block(block),
+            LedgerMode::Utxo => self.connect_utxo_block(block),
+        }
+    }
+
+    // 撤销 connect_block 的效果, 区块必须是最后一个连接的区块
+    pub fn disconnect_block(&mut self, block: &Block) -> Result<(), ChainError> {
+        match self.ledger {
+            LedgerMode::Account => self.disconnect_account_block(block),
+            LedgerMode::Utxo => self.disconnect_utxo_block(block),
+        }
+    }
+
+    fn connect_account_block(&mut self, block: &Block) -> Result<(), ChainError> {
+        let mut accounts = HashMap::new();
+        let mut stakes = self.stakes.clone();
+        for transaction in &block.transactions {
+            if transaction.is_spend() {
+                return Err(ChainError::LedgerMismatch { mode: self.ledger });
+            }
+            stakes.apply(transaction)?;
+            let cost = transaction.cost()?;
+            if !cost.is_zero() {
+                let address = transaction.sender();
+                let account = self.touch(&mut accounts, address);
+                account.balance =
+                    account
+                        .balance
+                        .checked_sub(cost)
+                        .ok_or_else(|| ChainError::Overspend {
+                            address: address.to_string(),
+                            available: account.balance,
+                            required: cost,
+                        })?;
+            }
+            if !transaction.is_coinbase() {
+                self.advance_nonce(&mut accounts, transaction)?;
+            }
+            if let Some(address) = transaction.credited_account() {
+                let account = self.touch(&mut accounts, address);
+                account.balance = account
+                    .balance
+                    .checked_add(transaction.amount())
+                    .ok_or(ChainError::AmountOverflow)?;
+            }
+        }
+        self.commit(accounts, stakes);
+        Ok(())
+    }
+
+    fn disconnect_account_block(&mut self, block: &Block) -> Result<(), ChainError> {
+        let mut accounts = HashMap::new();
+        let mut stakes = self.stakes.clone();
+        for transaction in block.transactions.iter().rev() {
+            if let Some(address) = transaction.credited_account() {
+                let account = self.touch(&mut accounts, address);
+                account.balance = account
+                    .balance
+                    .checked_sub(transaction.amount())
+                    .ok_or(ChainError::InconsistentState)?;
+            }
+            if !transaction.is_coinbase() {
+                let account = self.touch(&mut accounts, transaction.sender());
+                account.nonce = account
+                    .nonce
+                    .checked_sub(1)
+                    .ok_or(ChainError::InconsistentState)?;
+            }
+            let cost = transaction.cost()?;
+            if !cost.is_zero() {
+                let account = self.touch(&mut accounts, transaction.sender());
+                account.balance = account
+                    .balance
+                    .checked_add(cost)
+                    .ok_or(ChainError::InconsistentState)?;
+            }
+            stakes
+                .revert(transaction)
+                .map_err(|_| ChainError::InconsistentState)?;
+        }
+        self.commit(accounts, stakes);
+        Ok(())
+    }
+
+    // 检查交易的输入都存在, 属于 sender 且没有被 spent 中的交易花费, 返回输入总额
+    // created 为同一区块中前面的交易创建的输出
+    pub fn check_inputs(
+        &self,
+        transaction: &Transaction,
+        created: &HashMap<OutPoint, TxOutput>,
+        spent: &HashSet<OutPoint>,
+    ) -> Result<Amount, ChainError> {
+        if transaction.kind() != TxKind::Transfer || transaction.inputs().is_empty() {
+            return Err(ChainError::LedgerMismatch { mode: self.ledger });
+        }
+
+        let mut seen = HashSet::new();
+        let mut total = Amount::ZERO;
+        for input in transaction.inputs() {
+            if spent.contains(input) || !seen.insert(input) {
+                return Err(ChainError::DoubleSpend {
+                    outpoint: input.to_string(),
+                });
+            }
+            let output = created
+                .get(input)
+                .or_else(|| self.utxos.get(input))
+                .ok_or_else(|| ChainError::UnknownOutput {
+                    outpoint: input.to_string(),
+                })?;
+            if output.address != transaction.sender() {
+                return Err(ChainError::InputNotOwned {
+                    outpoint: input.to_string(),
+                });
+            }
+            total = total
+                .checked_add(output.amount)
+                .ok_or(ChainError::AmountOverflow)?;
+        }
+
+        // 输出总额必须等于签名的金额, 输入总额需要覆盖输出和手续费
+        let outputs = transaction.outputs();
+        let output_amount = outputs.iter().try_fold(Amount::ZERO, |sum, output| {
+            sum.checked_add(output.amount)
+                .ok_or(ChainError::AmountOverflow)
+        })?;
+        if output_amount != transaction.amount() {
+            return Err(ChainError::OutputsMismatch {
+                amount: transaction.amount(),
+                outputs: output_amount,
+            });
+        }
+        let output_total = output_amount
+            .checked_add(transaction.fee())
+            .ok_or(ChainError::AmountOverflow)?;
+        if output_total > total {
+            return Err(ChainError::OutputsExceedInputs {
+                inputs: total,
+                outputs: output_total,
+            });
+        }
+        Ok(total)
+    }
+
+    // 先校验整个区块再修改状态: 输入必须存在且未被花费, 输出不能超过输入
+    fn connect_utxo_block(&mut self, block: &Block) -> Result<(), ChainError> {
+        let mut accounts = HashMap::new();
+        let mut created: HashMap<OutPoint, TxOutput> = HashMap::new();
+        let mut spent = HashSet::new();
+        let mut spent_outputs = Vec::new();
+
+        for transaction in &block.transactions {
+            if transaction.is_coinbase() {
+                // 挖矿奖励只能付给 recipient, 显式输出可能绕过奖励上限
+                if transaction.is_spend() {
+                    return Err(ChainError::LedgerMismatch { mode: self.ledger });
+                }
+            } else {
+                self.check_inputs(transaction, &created, &spent)?;
+                for input in transaction.inputs() {
+                    let output = match created.remove(input) {
+                        Some(output) => output,
+                        None => self.utxos.get(input).cloned().unwrap_or_else(|| {
+                            unreachable!("check_inputs verified {input} exists")
+                        }),
+                    };
+                    let account = self.touch(&mut accounts, &output.address);
+                    account.balance = account
+                        .balance
+                        .checked_sub(output.amount)
+                        .ok_or(ChainError::InconsistentState)?;
+                    spent.insert(input.clone());
+                    spent_outputs.push((input.clone(), output));
+                }
+                self.advance_nonce(&mut accounts, transaction)?;
+            }
+
+            let txid = transaction.hash();
+            for (vout, output) in transaction.outputs().iter().enumerate() {
+                let outpoint = OutPoint::new(txid.clone(), vout as u32);
+                if self.utxos.contains(&outpoint) || created.contains_key(&outpoint) {
+                    return Err(ChainError::DuplicateOutput {
+                        outpoint: outpoint.to_string(),
+                    });
+                }
+                let account = self.touch(&mut accounts, &output.address);
+                account.balance = account
+                    .balance
+                    .checked_add(output.amount)
+                    .ok_or(ChainError::AmountOverflow)?;
+                created.insert(outpoint, output.clone());
+            }
+        }
+
+        // 同一区块内创建又花费的输出不会出现在 created 中
+        for (outpoint, _) in &spent_outputs {
+            self.utxos.remove(outpoint);
+        }
+        for (outpoint, output) in created {
+            self.utxos.insert(outpoint, output);
+        }
+        self.spent_outputs.insert(block.hash.clone(), spent_outputs);
+        self.commit(accounts, self.stakes.clone());
+        Ok(())
+    }
+
+    // 删除区块创建的输出并恢复它花费的输出
+    fn disconnect_utxo_block(&mut self, block: &Block) -> Result<(), ChainError> {
+        let spent_outputs = self
+            .spent_outputs
+            .get(&block.hash)
+            .ok_or(ChainError::InconsistentState)?;
+        let spent: HashSet<&OutPoint> =
+            spent_outputs.iter().map(|(outpoint, _)| outpoint).collect();
+        let txids: HashSet<String> = block.transactions.iter().map(|tx| tx.hash()).collect();
+        // 区块内创建又花费的输出既不删除也不恢复
+        let restored: Vec<(OutPoint, TxOutput)> = spent_outputs
+            .iter()
+            .filter(|(outpoint, _)| !txids.contains(&outpoint.txid))
+            .cloned()
+            .collect();
+
+        // 区块创建且仍未花费的输出
+        let mut created = Vec::new();
+        for transaction in &block.transactions {
+            let txid = transaction.hash();
+            for (vout, output) in transaction.outputs().iter().enumerate() {
+                let outpoint = OutPoint::new(txid.clone(), vout as u32);
+                if spent.contains(&outpoint) {
+                    continue;
+                }
+                if self.utxos.get(&outpoint) != Some(output) {
+                    return Err(ChainError::InconsistentState);
+                }
+                created.push((outpoint, output.clone()));
+            }
+        }
+
+        let mut accounts = HashMap::new();
+        for (_, output) in &created {
+            let account = self.touch(&mut accounts, &output.address);
+            account.balance = account
+                .balance
+                .checked_sub(output.amount)
+                .ok_or(ChainError::InconsistentState)?;
+        }
+        for (_, output) in &restored {
+            let account = self.touch(&mut accounts, &output.address);
+            account.balance = account
+                .balance
+                .checked_add(output.amount)
+                .ok_or(ChainError::InconsistentState)?;
+        }
+        for transaction in block.transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let account = self.touch(&mut accounts, transaction.sender());
+            account.nonce = account
+                .nonce
+                .checked_sub(1)
+                .ok_or(ChainError::InconsistentState)?;
+        }
+
+        for (outpoint, _) in &created {
+            self.utxos.remove(outpoint);
+        }
+        for (outpoint, output) in restored {
+            self.utxos.insert(outpoint, output);
+        }
+        self.spent_outputs.remove(&block.hash);
+        self.commit(accounts, self.stakes.clone());
+        Ok(())
+    }
+
+    // 交易序号必须等于 sender 已确认的交易数, 通过后递增
+    fn advance_nonce(
+        &self,
+        accounts: &mut HashMap<String, AccountState>,
+        transaction: &Transaction,
+    ) -> Result<(), ChainError> {
+        let account = self.touch(accounts, transaction.sender());
+        if transaction.nonce() != account.nonce {
+            return Err(ChainError::InvalidNonce {
+                address: transaction.sender().to_string(),
+                expected: account.nonce,
+                actual: transaction.nonce(),
+            });
+        }
+        account.nonce = account
+            .nonce
+            .checked_add(1)
+            .ok_or(ChainError::InconsistentState)?;
+        Ok(())
+    }
+
+    // 取出账户的待修改副本
+    fn touch<'a>(
+        &self,
+        accounts: &'a mut HashMap<String, AccountState>,
+        address: &str,
+    ) -> &'a mut AccountState {
+        accounts
+            .entry(address.to_string())
+            .or_insert_with(|| self.account(address))
+    }
+
+    fn commit(&mut self, accounts: HashMap<String, AccountState>, stakes: StakeTable) {
+        for (address, account) in accounts {
+            if account == AccountState::default() {
+                self.accounts.remove(&address);
+            } else {
+                self.accounts.insert(address, account);
+            }
+        }
+        self.stakes = stakes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::Transaction;
+    use crate::wallet::Wallet;
+
+    fn coins(n: u64) -> Amount {
+        Amount::from_coins(n).unwrap()
+    }
+
+    #[test]
+    fn test_connect_and_disconnect_round_trip() {
+        let alice = Wallet::generate();
+        let genesis = Block::new(
+            0,
+            vec![Transaction::coinbase(alice.address(), coins(100))],
+            "0".repeat(64),
+        );
+        let block = Block::new(
+            1,
+            vec![
+                alice.transfer("bob".to_string(), coins(30), 0),
+                alice.stake(coins(50), 1),
+                Transaction::coinbase("miner".to_string(), coins(10)),
+            ],
+            genesis.hash().to_string(),
+        );
+
+        let mut state =
+            ChainState::from_blocks(LedgerMode::Account, std::slice::from_ref(&genesis)).unwrap();
+        let before = state.clone();
+        state.connect_block(&block).unwrap();
+        assert_eq!(state.balance(&alice.address()), coins(20));
+        assert_eq!(state.nonce(&alice.address()), 2);
+        assert_eq!(state.balance("bob"), coins(30));
+        assert_eq!(state.stakes().stake_of(&alice.address()), coins(50));
+
+        state.disconnect_block(&block).unwrap();
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn test_failed_connect_leaves_state_unchanged() {
+        let alice = Wallet::generate();
+        let genesis = Block::new(
+            0,
+            vec![Transaction::coinbase(alice.address(), coins(10))],
+            "0".repeat(64),
+        );
+        let mut state =
+            ChainState::from_blocks(LedgerMode::Account, std::slice::from_ref(&genesis)).unwrap();
+        let before = state.clone();
+
+        // 第一笔转账有效, 第二笔超支, 整个区块都不生效
+        let block = Block::new(
+            1,
+            vec![
+                alice.transfer("bob".to_string(), coins(6), 0),
+                alice.transfer("carol".to_string(), coins(6), 1),
+            ],
+            genesis.hash().to_string(),
+        );
+        assert!(matches!(
+            state.connect_block(&block),
+            Err(ChainError::Overspend { .. })
+        ));
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn test_rejects_replayed_and_out_of_order_nonces() {
+        let alice = Wallet::generate();
+        let genesis = Block::new(
+            0,
+            vec![Transaction::coinbase(alice.address(), coins(100))],
+            "0".repeat(64),
+        );
+        let mut state =
+            ChainState::from_blocks(LedgerMode::Account, std::slice::from_ref(&genesis)).unwrap();
+
+        let transfer = alice.transfer("bob".to_string(), coins(10), 0);
+        let replayed = Block::new(
+            1,
+            vec![transfer.clone(), transfer.clone()],
+            genesis.hash().to_string(),
+        );
+        assert!(matches!(
+            state.connect_block(&replayed),
+            Err(ChainError::InvalidNonce {
+                expected: 1,
+                actual: 0,
+                ..
+            })
+        ));
+
+        let skipped = Block::new(
+            1,
+            vec![alice.transfer("bob".to_string(), coins(10), 1)],
+            genesis.hash().to_string(),
+        );
+        assert!(matches!(
+            state.connect_block(&skipped),
+            Err(ChainError::InvalidNonce {
+                expected: 0,
+                actual: 1,
+                ..
+            })
+        ));
+
+        let block = Block::new(1, vec![transfer], genesis.hash().to_string());
+        state.connect_block(&block).unwrap();
+        assert_eq!(state.nonce(&alice.address()), 1);
+    }
+
+    #[test]
+    fn test_utxo_connect_and_disconnect_round_trip() {
+        let alice = Wallet::generate();
+        let genesis = Block::new(
+            0,
+            vec![Transaction::coinbase(alice.address(), coins(100))],
+            "0".repeat(64),
+        );
+        let mut state =
+            ChainState::from_blocks(LedgerMode::Utxo, std::slice::from_ref(&genesis)).unwrap();
+        let funding = OutPoint::new(genesis.transactions[0].hash(), 0);
+        assert_eq!(state.balance(&alice.address()), coins(100));
+        assert!(state.utxos().contains(&funding));
+
+        // 同一区块内花费前一笔交易创建的找零输出
+        let first = alice.spend(
+            vec![funding.clone()],
+            vec![
+                TxOutput::new("bob".to_string(), coins(30)),
+                TxOutput::new(alice.address(), coins(70)),
+            ],
+            0,
+        );
+        let change = OutPoint::new(first.hash(), 1);
+        let second = alice.spend(
+            vec![change.clone()],
+            vec![TxOutput::new("carol".to_string(), coins(60))],
+            1,
+        );
+        let block = Block::new(1, vec![first, second], genesis.hash().to_string());
+
+        let before = state.clone();
+        state.connect_block(&block).unwrap();
+        assert_eq!(state.balance(&alice.address()), Amount::ZERO);
+        assert_eq!(state.balance("bob"), coins(30));
+        assert_eq!(state.balance("carol"), coins(60));
+        assert_eq!(state.nonce(&alice.address()), 2);
+        assert!(!state.utxos().contains(&funding));
+        assert!(!state.utxos().contains(&change));
+        assert_eq!(state.utxos().len(), 2);
+
+        state.disconnect_block(&block).unwrap();
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn test_utxo_rejects_double_spend() {
+        let alice = Wallet::generate();
+        let genesis = Block::new(
+            0,
+            vec![Transaction::coinbase(alice.address(), coins(100))],
+            "0".repeat(64),
+        );
+        let mut state =
+            ChainState::from_blocks(LedgerMode::Utxo, std::slice::from_ref(&genesis)).unwrap();
+        let before = state.clone();
+        let funding = OutPoint::new(genesis.transactions[0].hash(), 0);
+
+        let block = Block::new(
+            1,
+            vec![
+                alice.spend(
+                    vec![funding.clone()],
+                    vec![TxOutput::new("bob".to_string(), coins(100))],
+                    0,
+                ),
+                alice.spend(
+                    vec![funding.clone()],
+                    vec![TxOutput::new("carol".to_string(), coins(100))],
+                    1,
+                ),
+            ],
+            genesis.hash().to_string(),
+        );
+        assert!(matches!(
+            state.connect_block(&block),
+            Err(ChainError::DoubleSpend { .. })
+        ));
+        assert_eq!(state, before);
+
+        // 账户模式的转账不能出现在 UTXO 账本中
+        let block = Block::new(
+            1,
+            vec![alice.transfer("bob".to_string(), coins(10), 0)],
+            genesis.hash().to_string(),
+        );
+        assert!(matches!(
+            state.connect_block(&block),
+            Err(ChainError::LedgerMismatch { .. })
+        ));
+    }
+}
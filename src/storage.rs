@@ -0,0 +1,342 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::block::Block;
+use crate::error::ChainError;
+
+// 区块存储后端
+pub trait BlockStore: fmt::Debug + Send {
+    // 追加一个区块, 返回前必须已经落盘
+    fn append(&mut self, block: &Block) -> Result<(), ChainError>;
+
+    // 读取指定高度的区块
+    fn get(&mut self, height: u64) -> Result<Option<Block>, ChainError>;
+
+    // 按顺序读取所有区块
+    fn load_blocks(&mut self) -> Result<Vec<Block>, ChainError>;
+
+    // 丢弃高度不小于 height 的区块, 链重组时使用
+    fn truncate(&mut self, height: u64) -> Result<(), ChainError>;
+}
+
+// 内存存储, 主要用于测试
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    blocks: Vec<Block>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BlockStore for MemoryStore {
+    fn append(&mut self, block: &Block) -> Result<(), ChainError> {
+        self.blocks.push(block.clone());
+        Ok(())
+    }
+
+    fn get(&mut self, height: u64) -> Result<Option<Block>, ChainError> {
+        Ok(self.blocks.get(height as usize).cloned())
+    }
+
+    fn load_blocks(&mut self) -> Result<Vec<Block>, ChainError> {
+        Ok(self.blocks.clone())
+    }
+
+    fn truncate(&mut self, height: u64) -> Result<(), ChainError> {
+        self.blocks.truncate(height as usize);
+        Ok(())
+    }
+}
+
+const DATA_FILE: &str = "blocks.dat";
+const INDEX_FILE: &str = "blocks.idx";
+// 记录头: 4 字节长度 + 4 字节校验和
+const RECORD_HEADER_LEN: u64 = 8;
+
+// 文件存储: 只追加的区块文件 + 记录偏移量的索引文件
+//
+// blocks.dat 中每条记录为 [长度 u32][sha256 前 4 字节][JSON], blocks.idx 中
+// 每个区块占 8 字节偏移量. 打开时会丢弃末尾写了一半的记录并重建索引,
+// 中间的记录损坏时返回错误而不截断.
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    data: File,
+    index: File,
+    offsets: Vec<u64>,
+    end: u64,
+}
+
+impl FileStore {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, ChainError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut data = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(DATA_FILE))?;
+        let (offsets, end) = Self::scan(&mut data)?;
+
+        // 截断不完整的尾部记录
+        if data.metadata()?.len() != end {
+            data.set_len(end)?;
+            data.sync_all()?;
+        }
+
+        let mut index = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(INDEX_FILE))?;
+        Self::rebuild_index_if_needed(&mut index, &offsets)?;
+
+        Ok(FileStore {
+            dir,
+            data,
+            index,
+            offsets,
+            end,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // 从头扫描数据文件, 返回所有完整记录的偏移量和有效数据的结尾
+    // 校验和错误的记录不是最后一条时返回 InvalidData
+    fn scan(data: &mut File) -> io::Result<(Vec<u64>, u64)> {
+        let len = data.metadata()?.len();
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        data.seek(SeekFrom::Start(0))?;
+
+        while offset + RECORD_HEADER_LEN <= len {
+            let mut header = [0u8; RECORD_HEADER_LEN as usize];
+            data.read_exact(&mut header)?;
+            let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64;
+            if offset + RECORD_HEADER_LEN + payload_len > len {
+                break;
+            }
+
+            // 只有最后一条记录可能是写了一半的记录, 之后还有数据时说明文件已损坏
+            let mut payload = vec![0u8; payload_len as usize];
+            data.read_exact(&mut payload)?;
+            if header[4..] != checksum(&payload) {
+                if offset + RECORD_HEADER_LEN + payload_len == len {
+                    break;
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("corrupt block record at offset {offset}"),
+                ));
+            }
+
+            offsets.push(offset);
+            offset += RECORD_HEADER_LEN + payload_len;
+        }
+        Ok((offsets, offset))
+    }
+
+    fn rebuild_index_if_needed(index: &mut File, offsets: &[u64]) -> io::Result<()> {
+        let expected: Vec<u8> = offsets.iter().flat_map(|o| o.to_le_bytes()).collect();
+        let mut existing = Vec::new();
+        index.seek(SeekFrom::Start(0))?;
+        index.read_to_end(&mut existing)?;
+        if existing != expected {
+            index.set_len(0)?;
+            index.seek(SeekFrom::Start(0))?;
+            index.write_all(&expected)?;
+            index.sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn read_record(&mut self, offset: u64) -> Result<Block, ChainError> {
+        let mut header = [0u8; RECORD_HEADER_LEN as usize];
+        self.data.seek(SeekFrom::Start(offset))?;
+        self.data.read_exact(&mut header)?;
+        let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let mut payload = vec![0u8; payload_len];
+        self.data.read_exact(&mut payload)?;
+        Ok(serde_json::from_slice(&payload).map_err(io::Error::from)?)
+    }
+}
+
+impl BlockStore for FileStore {
+    fn append(&mut self, block: &Block) -> Result<(), ChainError> {
+        let payload = serde_json::to_vec(block).map_err(io::Error::from)?;
+        let payload_len = u32::try_from(payload.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "block too large"))?;
+
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN as usize + payload.len());
+        record.extend_from_slice(&payload_len.to_le_bytes());
+        record.extend_from_slice(&checksum(&payload));
+        record.extend_from_slice(&payload);
+
+        // 先写数据并落盘, 再写索引; 索引缺失的条目会在下次打开时重建
+        self.data.seek(SeekFrom::Start(self.end))?;
+        self.data.write_all(&record)?;
+        self.data.sync_data()?;
+
+        self.index.seek(SeekFrom::End(0))?;
+        self.index.write_all(&self.end.to_le_bytes())?;
+        self.index.sync_data()?;
+
+        self.offsets.push(self.end);
+        self.end += record.len() as u64;
+        Ok(())
+    }
+
+    fn get(&mut self, height: u64) -> Result<Option<Block>, ChainError> {
+        match self.offsets.get(height as usize) {
+            Some(&offset) => self.read_record(offset).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn load_blocks(&mut self) -> Result<Vec<Block>, ChainError> {
+        let offsets = self.offsets.clone();
+        offsets
+            .into_iter()
+            .map(|offset| self.read_record(offset))
+            .collect()
+    }
+
+    // 先截断数据文件再截断索引, 中途崩溃时打开会按数据文件重建索引
+    fn truncate(&mut self, height: u64) -> Result<(), ChainError> {
+        let Some(&end) = self.offsets.get(height as usize) else {
+            return Ok(());
+        };
+        self.data.set_len(end)?;
+        self.data.sync_all()?;
+        self.index.set_len(height * 8)?;
+        self.index.sync_all()?;
+
+        self.offsets.truncate(height as usize);
+        self.end = end;
+        Ok(())
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let digest = Sha256::digest(payload);
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("blockchain-store-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_file_store_reopen() {
+        let dir = temp_dir();
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let block = Block::new(1, vec![], genesis.hash().to_string());
+        {
+            let mut store = FileStore::open(&dir).unwrap();
+            store.append(&genesis).unwrap();
+            store.append(&block).unwrap();
+        }
+
+        let mut store = FileStore::open(&dir).unwrap();
+        let blocks = store.load_blocks().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].hash(), block.hash());
+        assert_eq!(store.get(1).unwrap().unwrap().hash(), block.hash());
+        assert!(store.get(2).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_truncates_torn_tail() {
+        let dir = temp_dir();
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let valid_len = {
+            let mut store = FileStore::open(&dir).unwrap();
+            store.append(&genesis).unwrap();
+            store.end
+        };
+
+        // 模拟写到一半时崩溃: 只写入了记录头和部分数据
+        let mut data = OpenOptions::new()
+            .append(true)
+            .open(dir.join(DATA_FILE))
+            .unwrap();
+        data.write_all(&[200, 0, 0, 0, 1, 2, 3, 4, b'{']).unwrap();
+        drop(data);
+
+        let mut store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.load_blocks().unwrap().len(), 1);
+        assert_eq!(fs::metadata(dir.join(DATA_FILE)).unwrap().len(), valid_len);
+        assert_eq!(fs::metadata(dir.join(INDEX_FILE)).unwrap().len(), 8);
+
+        // 截断后可以继续追加
+        let block = Block::new(1, vec![], genesis.hash().to_string());
+        store.append(&block).unwrap();
+        drop(store);
+        let mut store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.load_blocks().unwrap().len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_rejects_corrupt_record() {
+        let dir = temp_dir();
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let block = Block::new(1, vec![], genesis.hash().to_string());
+        let len = {
+            let mut store = FileStore::open(&dir).unwrap();
+            store.append(&genesis).unwrap();
+            store.append(&block).unwrap();
+            store.end
+        };
+
+        // 修改第一条记录中的一个字节, 之后的记录仍然完整
+        let path = dir.join(DATA_FILE);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[RECORD_HEADER_LEN as usize + 1] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+
+        assert!(matches!(FileStore::open(&dir), Err(ChainError::Storage(_))));
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_truncate_for_reorg() {
+        let dir = temp_dir();
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let mut block = Block::new(1, vec![], genesis.hash().to_string());
+        let mut store = FileStore::open(&dir).unwrap();
+        store.append(&genesis).unwrap();
+        store.append(&block).unwrap();
+
+        // 用另一个分支的区块替换高度 1
+        store.truncate(1).unwrap();
+        block.nonce = 1;
+        block.hash = block.calculate_hash();
+        store.append(&block).unwrap();
+        drop(store);
+
+        let mut store = FileStore::open(&dir).unwrap();
+        let blocks = store.load_blocks().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].hash(), block.hash());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
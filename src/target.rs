@@ -0,0 +1,133 @@
+use primitive_types::U256;
+use std::fmt;
+
+// 256 位工作量证明目标值, 区块哈希 (按大端整数解释) 不大于目标值即有效
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Target(U256);
+
+impl Target {
+    // 最容易的目标值
+    pub const MAX: Target = Target(U256::MAX);
+
+    pub fn from_u256(value: U256) -> Self {
+        Target(value)
+    }
+
+    pub fn as_u256(&self) -> U256 {
+        self.0
+    }
+
+    // 要求哈希至少有 zeros 个前导零比特的目标值
+    pub fn from_leading_zeros(zeros: u32) -> Self {
+        if zeros >= 256 {
+            return Target(U256::zero());
+        }
+        Target(U256::MAX >> zeros as usize)
+    }
+
+    // 解码比特币 nBits 格式的紧凑表示: 高 8 位为字节长度, 低 23 位为尾数
+    // 负数或超出 256 位的编码返回 None
+    pub fn from_compact(bits: u32) -> Option<Self> {
+        let size = (bits >> 24) as usize;
+        let mantissa = bits & 0x007f_ffff;
+        if mantissa != 0 && bits & 0x0080_0000 != 0 {
+            return None;
+        }
+
+        let value = if size <= 3 {
+            U256::from(mantissa >> (8 * (3 - size)))
+        } else {
+            let shift = 8 * (size - 3);
+            let value = U256::from(mantissa);
+            if mantissa != 0 && value.bits() + shift > 256 {
+                return None;
+            }
+            value << shift
+        };
+        Some(Target(value))
+    }
+
+    // 编码为紧凑表示, 只保留最高 3 个字节的精度
+    pub fn to_compact(&self) -> u32 {
+        let mut size = self.0.bits().div_ceil(8);
+        let mut mantissa = if size <= 3 {
+            self.0.low_u32() << (8 * (3 - size))
+        } else {
+            (self.0 >> (8 * (size - 3))).low_u32()
+        };
+        // 尾数最高位是符号位, 需要时多占用一个字节
+        if mantissa & 0x0080_0000 != 0 {
+            mantissa >>= 8;
+            size += 1;
+        }
+        mantissa | ((size as u32) << 24)
+    }
+
+    pub fn is_met_by(&self, hash: &[u8; 32]) -> bool {
+        U256::from_big_endian(hash) <= self.0
+    }
+
+    // 找到满足该目标的哈希平均需要尝试的次数: 2^256 / (target + 1)
+    // 目标值为 0 时结果超出 256 位, 取 U256::MAX
+    pub fn work(&self) -> U256 {
+        if self.0 == U256::MAX {
+            return U256::one();
+        }
+        (!self.0 / (self.0 + 1)).saturating_add(U256::one())
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:064x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_round_trip() {
+        // 比特币创世区块的难度
+        let target = Target::from_compact(0x1d00ffff).unwrap();
+        assert_eq!(target.as_u256(), U256::from(0xffffu64) << 208);
+        assert_eq!(target.to_compact(), 0x1d00ffff);
+
+        for zeros in [0, 1, 4, 9, 16, 20, 255] {
+            let target = Target::from_leading_zeros(zeros);
+            let decoded = Target::from_compact(target.to_compact()).unwrap();
+            // 紧凑编码会截断低位, 但不会让目标值变大
+            assert!(decoded <= target);
+            assert_eq!(decoded.to_compact(), target.to_compact());
+        }
+    }
+
+    #[test]
+    fn test_invalid_compact_rejected() {
+        assert!(Target::from_compact(0x04923456).is_none());
+        assert!(Target::from_compact(0xff123456).is_none());
+        assert_eq!(
+            Target::from_compact(0x01003456).unwrap().as_u256(),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn test_hash_against_target() {
+        let target = Target::from_leading_zeros(12);
+        let mut hash = [0u8; 32];
+        hash[1] = 0x0f;
+        assert!(target.is_met_by(&hash));
+        hash[1] = 0x10;
+        assert!(!target.is_met_by(&hash));
+    }
+
+    #[test]
+    fn test_work_grows_with_difficulty() {
+        assert_eq!(Target::MAX.work(), U256::one());
+        assert_eq!(Target::from_leading_zeros(1).work(), U256::from(2));
+        assert_eq!(Target::from_leading_zeros(16).work(), U256::from(65536));
+        assert_eq!(Target::from_u256(U256::zero()).work(), U256::MAX);
+    }
+}
@@ -0,0 +1,394 @@
+use primitive_types::U256;
+use std::collections::HashMap;
+
+use crate::block::Block;
+use crate::error::ChainError;
+
+// 区块树中的节点, chain_work 为从创世区块到本区块的累计工作量
+#[derive(Debug, Clone)]
+struct TreeNode {
+    block: Block,
+    chain_work: U256,
+    // 加入区块树的顺序, 累计工作量相同时先加入的分支优先
+    sequence: u64,
+}
+
+// 孤块池中的区块, added_at 为到达时间, sequence 为到达顺序
+#[derive(Debug, Clone)]
+struct OrphanEntry {
+    block: Block,
+    added_at: i64,
+    sequence: u64,
+}
+
+// 以哈希为键的区块树: 保存所有已知分支, 累计工作量最大的分支为主链
+// 父区块未知的区块暂存在孤块池中, 等父区块到达后再连接
+#[derive(Debug, Clone)]
+pub struct BlockTree {
+    nodes: HashMap<String, TreeNode>,
+    children: HashMap<String, Vec<String>>,
+    orphans: HashMap<String, OrphanEntry>,
+    next_sequence: u64,
+    next_orphan_sequence: u64,
+    genesis_hash: String,
+    best_tip: String,
+}
+
+impl BlockTree {
+    pub fn new(genesis: Block, work: U256) -> Self {
+        let genesis_hash = genesis.hash.clone();
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            genesis_hash.clone(),
+            TreeNode {
+                block: genesis,
+                chain_work: work,
+                sequence: 0,
+            },
+        );
+        BlockTree {
+            nodes,
+            children: HashMap::new(),
+            orphans: HashMap::new(),
+            next_sequence: 1,
+            next_orphan_sequence: 0,
+            best_tip: genesis_hash.clone(),
+            genesis_hash,
+        }
+    }
+
+    // 把父区块已知的区块加入树中, work 为该区块自身的工作量
+    // 累计工作量严格大于当前主链时切换最佳链尾, 相同时保留先收到的分支
+    pub fn insert(&mut self, block: Block, work: U256) -> Result<(), ChainError> {
+        if self.contains(&block.hash) {
+            return Err(ChainError::DuplicateBlock {
+                hash: block.hash.clone(),
+            });
+        }
+        let parent = self
+            .nodes
+            .get(&block.previous_hash)
+            .filter(|parent| parent.block.index + 1 == block.index)
+            .ok_or(ChainError::BrokenLink { index: block.index })?;
+
+        let chain_work = parent.chain_work.saturating_add(work);
+        let hash = block.hash.clone();
+        self.children
+            .entry(block.previous_hash.clone())
+            .or_default()
+            .push(hash.clone());
+        self.orphans.remove(&hash);
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.nodes.insert(
+            hash.clone(),
+            TreeNode {
+                block,
+                chain_work,
+                sequence,
+            },
+        );
+        if chain_work > self.best_chain_work() {
+            self.best_tip = hash;
+        }
+        Ok(())
+    }
+
+    // 移除区块及其所有后代, 返回被移除的区块, 不能移除创世区块
+    // 最佳链尾被移除时重新选择累计工作量最大且最先加入的链尾
+    pub fn remove(&mut self, hash: &str) -> Vec<Block> {
+        if hash == self.genesis_hash {
+            return Vec::new();
+        }
+        let Some(node) = self.nodes.get(hash) else {
+            return Vec::new();
+        };
+        if let Some(siblings) = self.children.get_mut(&node.block.previous_hash) {
+            siblings.retain(|child| child != hash);
+            if siblings.is_empty() {
+                let parent = node.block.previous_hash.clone();
+                self.children.remove(&parent);
+            }
+        }
+
+        let mut removed = Vec::new();
+        let mut pending = vec![hash.to_string()];
+        while let Some(hash) = pending.pop() {
+            pending.extend(self.children.remove(&hash).unwrap_or_default());
+            if let Some(node) = self.nodes.remove(&hash) {
+                removed.push(node.block);
+            }
+        }
+
+        if !self.nodes.contains_key(&self.best_tip) {
+            let best = self
+                .nodes
+                .iter()
+                .max_by(|(_, a), (_, b)| {
+                    a.chain_work
+                        .cmp(&b.chain_work)
+                        .then(b.sequence.cmp(&a.sequence))
+                })
+                .map(|(hash, _)| hash.clone());
+            self.best_tip = best.unwrap_or_else(|| self.genesis_hash.clone());
+        }
+        removed
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.nodes.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Block> {
+        self.nodes.get(hash).map(|node| &node.block)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn genesis(&self) -> &Block {
+        &self.nodes[&self.genesis_hash].block
+    }
+
+    // 从创世区块到 hash 的累计工作量
+    pub fn chain_work(&self, hash: &str) -> Option<U256> {
+        self.nodes.get(hash).map(|node| node.chain_work)
+    }
+
+    // 累计工作量最大的链尾
+    pub fn best_tip(&self) -> &Block {
+        &self.nodes[&self.best_tip].block
+    }
+
+    pub fn best_chain_work(&self) -> U256 {
+        self.nodes[&self.best_tip].chain_work
+    }
+
+    // 从创世区块到 hash 的整条分支
+    pub fn chain_to(&self, hash: &str) -> Option<Vec<Block>> {
+        let mut blocks = Vec::new();
+        let mut current = self.nodes.get(hash)?;
+        loop {
+            blocks.push(current.block.clone());
+            match self.nodes.get(&current.block.previous_hash) {
+                Some(parent) if current.block.index > 0 => current = parent,
+                _ => break,
+            }
+        }
+        blocks.reverse();
+        Some(blocks)
+    }
+
+    // 当前主链
+    pub fn active_chain(&self) -> Vec<Block> {
+        self.chain_to(&self.best_tip).unwrap_or_default()
+    }
+
+    // 没有子区块的区块, 即各分支的链尾
+    pub fn tips(&self) -> Vec<&Block> {
+        self.nodes
+            .iter()
+            .filter(|(hash, _)| !self.children.contains_key(*hash))
+            .map(|(_, node)| &node.block)
+            .collect()
+    }
+
+    // 有多个子区块的区块, 即分叉点
+    pub fn fork_points(&self) -> Vec<&Block> {
+        self.children
+            .iter()
+            .filter(|(_, children)| children.len() > 1)
+            .filter_map(|(hash, _)| self.get(hash))
+            .collect()
+    }
+
+    // 两个区块所在分支的最近公共祖先
+    pub fn common_ancestor(&self, a: &str, b: &str) -> Option<&Block> {
+        let mut a = self.get(a)?;
+        let mut b = self.get(b)?;
+        while a.hash != b.hash {
+            if a.index >= b.index {
+                a = self.get(&a.previous_hash)?;
+            } else {
+                b = self.get(&b.previous_hash)?;
+            }
+        }
+        Some(a)
+    }
+
+    // 暂存父区块未知的区块, now 为到达时间
+    pub fn add_orphan(&mut self, block: Block, now: i64) -> Result<(), ChainError> {
+        if self.contains(&block.hash) || self.orphans.contains_key(&block.hash) {
+            return Err(ChainError::DuplicateBlock {
+                hash: block.hash.clone(),
+            });
+        }
+        let entry = OrphanEntry {
+            block,
+            added_at: now,
+            sequence: self.next_orphan_sequence,
+        };
+        self.next_orphan_sequence += 1;
+        self.orphans.insert(entry.block.hash.clone(), entry);
+        Ok(())
+    }
+
+    pub fn orphans(&self) -> impl Iterator<Item = &Block> {
+        self.orphans.values().map(|entry| &entry.block)
+    }
+
+    pub fn is_orphan(&self, hash: &str) -> bool {
+        self.orphans.contains_key(hash)
+    }
+
+    // 移除最早到达的孤块, 孤块池为空时返回 None
+    pub fn evict_oldest_orphan(&mut self) -> Option<Block> {
+        let hash = self
+            .orphans
+            .values()
+            .min_by_key(|entry| entry.sequence)
+            .map(|entry| entry.block.hash.clone())?;
+        self.orphans.remove(&hash).map(|entry| entry.block)
+    }
+
+    // 移除在 cutoff 之前到达的孤块
+    pub fn expire_orphans(&mut self, cutoff: i64) {
+        self.orphans.retain(|_, entry| entry.added_at >= cutoff);
+    }
+
+    // 取出父区块为 parent_hash 的孤块, 按哈希排序
+    pub fn take_orphans(&mut self, parent_hash: &str) -> Vec<Block> {
+        let mut hashes: Vec<String> = self
+            .orphans
+            .values()
+            .filter(|entry| entry.block.previous_hash == parent_hash)
+            .map(|entry| entry.block.hash.clone())
+            .collect();
+        hashes.sort();
+        hashes
+            .iter()
+            .filter_map(|hash| self.orphans.remove(hash))
+            .map(|entry| entry.block)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(parent: &Block, nonce: u64) -> Block {
+        let mut block = Block::new(parent.index + 1, vec![], parent.hash.clone());
+        block.nonce = nonce;
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    #[test]
+    fn test_heaviest_branch_becomes_best_tip() {
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let mut tree = BlockTree::new(genesis.clone(), U256::one());
+
+        // 主链 genesis -> a1 -> a2, 分支 genesis -> b1
+        let a1 = child(&genesis, 1);
+        let a2 = child(&a1, 1);
+        let b1 = child(&genesis, 2);
+        tree.insert(a1.clone(), U256::one()).unwrap();
+        tree.insert(a2.clone(), U256::one()).unwrap();
+        tree.insert(b1.clone(), U256::one()).unwrap();
+        assert_eq!(tree.best_tip().hash, a2.hash);
+        assert_eq!(tree.best_chain_work(), U256::from(3));
+        assert_eq!(tree.tips().len(), 2);
+        assert_eq!(tree.fork_points()[0].hash, genesis.hash);
+        assert_eq!(
+            tree.common_ancestor(&a2.hash, &b1.hash).unwrap().hash,
+            genesis.hash
+        );
+
+        // 工作量相同时保留先收到的分支, 更重时切换
+        let b2 = child(&b1, 1);
+        tree.insert(b2.clone(), U256::one()).unwrap();
+        assert_eq!(tree.best_tip().hash, a2.hash);
+        let b3 = child(&b2, 1);
+        tree.insert(b3.clone(), U256::one()).unwrap();
+        assert_eq!(tree.best_tip().hash, b3.hash);
+
+        let hashes: Vec<String> = tree.active_chain().into_iter().map(|b| b.hash).collect();
+        assert_eq!(
+            hashes,
+            [&genesis.hash, &b1.hash, &b2.hash, &b3.hash].map(String::clone)
+        );
+        assert!(matches!(
+            tree.insert(b3, U256::one()),
+            Err(ChainError::DuplicateBlock { .. })
+        ));
+    }
+
+    #[test]
+    fn test_remove_branch_restores_best_tip() {
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let mut tree = BlockTree::new(genesis.clone(), U256::one());
+        let a1 = child(&genesis, 1);
+        let b1 = child(&genesis, 2);
+        let b2 = child(&b1, 2);
+        tree.insert(a1.clone(), U256::one()).unwrap();
+        tree.insert(b1.clone(), U256::one()).unwrap();
+        tree.insert(b2.clone(), U256::one()).unwrap();
+        tree.insert(child(&genesis, 3), U256::one()).unwrap();
+        assert_eq!(tree.best_tip().hash, b2.hash);
+
+        // 移除分支后工作量相同的链尾中先加入的成为最佳链尾
+        let removed = tree.remove(&b1.hash);
+        assert_eq!(removed.len(), 2);
+        assert_eq!(tree.best_tip().hash, a1.hash);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.tips().len(), 2);
+        assert!(tree.remove(&genesis.hash).is_empty());
+    }
+
+    #[test]
+    fn test_orphans_wait_for_parent() {
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let mut tree = BlockTree::new(genesis.clone(), U256::one());
+        let a1 = child(&genesis, 1);
+        let a2 = child(&a1, 1);
+
+        assert!(matches!(
+            tree.insert(a2.clone(), U256::one()),
+            Err(ChainError::BrokenLink { index: 2 })
+        ));
+        tree.add_orphan(a2.clone(), 0).unwrap();
+        assert!(tree.is_orphan(&a2.hash));
+
+        tree.insert(a1.clone(), U256::one()).unwrap();
+        let orphans = tree.take_orphans(&a1.hash);
+        assert_eq!(orphans.len(), 1);
+        tree.insert(orphans[0].clone(), U256::one()).unwrap();
+        assert_eq!(tree.best_tip().hash, a2.hash);
+        assert_eq!(tree.orphans().count(), 0);
+    }
+
+    #[test]
+    fn test_orphans_evicted_by_age() {
+        let genesis = Block::new(0, vec![], "0".repeat(64));
+        let mut tree = BlockTree::new(genesis.clone(), U256::one());
+        let a1 = child(&genesis, 1);
+        let (a2, b2, c2) = (child(&a1, 1), child(&a1, 2), child(&a1, 3));
+        tree.add_orphan(a2.clone(), 100).unwrap();
+        tree.add_orphan(b2.clone(), 100).unwrap();
+        tree.add_orphan(c2.clone(), 200).unwrap();
+
+        // 同一时间到达时按到达顺序淘汰
+        assert_eq!(tree.evict_oldest_orphan().unwrap().hash, a2.hash);
+        tree.expire_orphans(150);
+        assert!(!tree.is_orphan(&b2.hash));
+        assert!(tree.is_orphan(&c2.hash));
+        assert_eq!(tree.evict_oldest_orphan().unwrap().hash, c2.hash);
+        assert!(tree.evict_oldest_orphan().is_none());
+    }
+}
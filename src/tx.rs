@@ -0,0 +1,348 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+
+use crate::amount::Amount;
+use crate::error::ChainError;
+use crate::utxo::{OutPoint, TxOutput};
+use crate::wallet::{self, Wallet};
+
+// 挖矿奖励交易的发送方, 不需要签名
+pub const COINBASE_SENDER: &str = "System";
+
+// 交易类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TxKind {
+    // 从 sender 转账给 recipient
+    #[default]
+    Transfer,
+    // 把 sender 的可用余额锁定为质押
+    Stake,
+    // 解除 sender 的质押, 退回可用余额
+    Unstake,
+}
+
+impl TxKind {
+    fn tag(&self) -> u8 {
+        match self {
+            TxKind::Transfer => 0,
+            TxKind::Stake => 1,
+            TxKind::Unstake => 2,
+        }
+    }
+}
+
+// 交易结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    #[serde(default)]
+    kind: TxKind,
+    // 发送方公钥 (十六进制), 挖矿奖励为 "System"
+    sender: String,
+    recipient: String,
+    amount: Amount,
+    // 付给打包该交易的矿工的手续费, 由 sender 支付
+    #[serde(default)]
+    fee: Amount,
+    timestamp: i64,
+    // sender 的交易序号, 必须等于 sender 已确认的交易数, 防止交易被重放
+    #[serde(default)]
+    nonce: u64,
+    // 挖矿奖励交易中的额外随机数, nonce 用尽时递增以改变默克尔根
+    #[serde(default)]
+    extra_nonce: u64,
+    // UTXO 模式下花费的输出, 必须都属于 sender
+    #[serde(default)]
+    inputs: Vec<OutPoint>,
+    // UTXO 模式下创建的输出, 为空时视为付给 recipient 的单个输出
+    #[serde(default)]
+    outputs: Vec<TxOutput>,
+    signature: Option<String>,
+}
+
+impl Transaction {
+    pub fn new(sender: String, recipient: String, amount: Amount) -> Self {
+        Transaction {
+            kind: TxKind::Transfer,
+            sender,
+            recipient,
+            amount,
+            fee: Amount::ZERO,
+            timestamp: Utc::now().timestamp(),
+            nonce: 0,
+            extra_nonce: 0,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            signature: None,
+        }
+    }
+
+    // 挖矿奖励交易
+    pub fn coinbase(recipient: String, amount: Amount) -> Self {
+        Transaction::new(COINBASE_SENDER.to_string(), recipient, amount)
+    }
+
+    // 质押交易, 收款方即质押者本人
+    pub fn stake(staker: String, amount: Amount) -> Self {
+        Transaction {
+            kind: TxKind::Stake,
+            ..Transaction::new(staker.clone(), staker, amount)
+        }
+    }
+
+    pub fn unstake(staker: String, amount: Amount) -> Self {
+        Transaction {
+            kind: TxKind::Unstake,
+            ..Transaction::new(staker.clone(), staker, amount)
+        }
+    }
+
+    // UTXO 模式的转账: 花费 sender 的 inputs, 创建 outputs, amount 为输出总额
+    pub fn spend(sender: String, inputs: Vec<OutPoint>, outputs: Vec<TxOutput>) -> Self {
+        let amount = outputs.iter().fold(Amount::ZERO, |total, output| {
+            total.saturating_add(output.amount)
+        });
+        Transaction {
+            inputs,
+            outputs,
+            ..Transaction::new(sender, String::new(), amount)
+        }
+    }
+
+    // 设置交易序号, 必须在签名之前调用
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    // 设置手续费, 必须在签名之前调用
+    pub fn with_fee(mut self, fee: Amount) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.sender == COINBASE_SENDER
+    }
+
+    pub fn kind(&self) -> TxKind {
+        self.kind
+    }
+
+    // 可用余额减少的账户
+    pub fn debited_account(&self) -> Option<&str> {
+        match self.kind {
+            TxKind::Transfer | TxKind::Stake if !self.is_coinbase() => Some(&self.sender),
+            _ => None,
+        }
+    }
+
+    // 可用余额增加的账户, 挖矿奖励总是付给 recipient
+    pub fn credited_account(&self) -> Option<&str> {
+        match self.kind {
+            TxKind::Stake if !self.is_coinbase() => None,
+            TxKind::Unstake if !self.is_coinbase() => Some(&self.sender),
+            _ => Some(&self.recipient),
+        }
+    }
+
+    pub fn sender(&self) -> &str {
+        &self.sender
+    }
+
+    pub fn recipient(&self) -> &str {
+        &self.recipient
+    }
+
+    pub fn amount(&self) -> Amount {
+        self.amount
+    }
+
+    pub fn fee(&self) -> Amount {
+        self.fee
+    }
+
+    // sender 可用余额减少的总额: 转出或质押的金额加上手续费
+    pub fn cost(&self) -> Result<Amount, ChainError> {
+        if self.is_coinbase() {
+            return Ok(Amount::ZERO);
+        }
+        let debited = match self.debited_account() {
+            Some(_) => self.amount,
+            None => Amount::ZERO,
+        };
+        debited
+            .checked_add(self.fee)
+            .ok_or(ChainError::AmountOverflow)
+    }
+
+    // 编码后的字节数, 用于计算手续费率和区块大小
+    pub fn size(&self) -> usize {
+        self.encode().len()
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn extra_nonce(&self) -> u64 {
+        self.extra_nonce
+    }
+
+    pub(crate) fn set_extra_nonce(&mut self, extra_nonce: u64) {
+        self.extra_nonce = extra_nonce;
+    }
+
+    // 是否带有 UTXO 模式的输入或显式输出
+    pub fn is_spend(&self) -> bool {
+        !self.inputs.is_empty() || !self.outputs.is_empty()
+    }
+
+    pub fn inputs(&self) -> &[OutPoint] {
+        &self.inputs
+    }
+
+    // 交易创建的输出, 没有显式输出的交易 (如挖矿奖励) 付给 recipient
+    pub fn outputs(&self) -> Cow<'_, [TxOutput]> {
+        if self.outputs.is_empty() {
+            Cow::Owned(vec![TxOutput::new(self.recipient.clone(), self.amount)])
+        } else {
+            Cow::Borrowed(&self.outputs)
+        }
+    }
+
+    pub fn signature(&self) -> Option<&str> {
+        self.signature.as_deref()
+    }
+
+    // 签名使用的规范编码: 每个字段按固定顺序写入, 字符串带长度前缀
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.kind.tag()];
+        for field in [&self.sender, &self.recipient] {
+            bytes.extend_from_slice(&(field.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(field.as_bytes());
+        }
+        bytes.extend_from_slice(&self.amount.base_units().to_le_bytes());
+        bytes.extend_from_slice(&self.fee.base_units().to_le_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        bytes.extend_from_slice(&self.extra_nonce.to_le_bytes());
+        bytes.extend_from_slice(&(self.inputs.len() as u64).to_le_bytes());
+        for input in &self.inputs {
+            bytes.extend_from_slice(&(input.txid.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(input.txid.as_bytes());
+            bytes.extend_from_slice(&input.vout.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.outputs.len() as u64).to_le_bytes());
+        for output in &self.outputs {
+            bytes.extend_from_slice(&(output.address.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(output.address.as_bytes());
+            bytes.extend_from_slice(&output.amount.base_units().to_le_bytes());
+        }
+        bytes
+    }
+
+    // 计算区块哈希时使用的完整编码, 包含签名
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.signing_bytes();
+        let signature = self.signature.as_deref().unwrap_or_default();
+        bytes.extend_from_slice(&(signature.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(signature.as_bytes());
+        bytes
+    }
+
+    // 交易哈希 (txid), 作为默克尔树的叶子
+    pub fn hash_bytes(&self) -> [u8; 32] {
+        Sha256::digest(self.encode()).into()
+    }
+
+    pub fn hash(&self) -> String {
+        hex::encode(self.hash_bytes())
+    }
+
+    pub fn sign(&mut self, wallet: &Wallet) {
+        self.signature = Some(wallet.sign(&self.signing_bytes()));
+    }
+
+    // 验证签名是否由 sender 对应的私钥生成
+    pub fn verify_signature(&self) -> Result<(), ChainError> {
+        if self.is_coinbase() {
+            return Err(ChainError::UnexpectedCoinbase);
+        }
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(ChainError::MissingSignature)?;
+
+        wallet::verify_signature(&self.sender, &self.signing_bytes(), signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_transaction_verifies() {
+        let wallet = Wallet::generate();
+        let transaction =
+            wallet.transfer("recipient".to_string(), Amount::from_coins(10).unwrap(), 0);
+        assert!(transaction.verify_signature().is_ok());
+    }
+
+    #[test]
+    fn test_tampered_transaction_rejected() {
+        let wallet = Wallet::generate();
+        let mut transaction =
+            wallet.transfer("recipient".to_string(), Amount::from_coins(10).unwrap(), 0);
+        transaction.amount = Amount::from_coins(1000).unwrap();
+        assert!(matches!(
+            transaction.verify_signature(),
+            Err(ChainError::InvalidSignature)
+        ));
+
+        // 手续费同样受签名保护
+        let mut transaction =
+            wallet.transfer("recipient".to_string(), Amount::from_coins(10).unwrap(), 0);
+        transaction.fee = Amount::from_coins(1).unwrap();
+        assert!(transaction.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_stake_moves_balance_out_of_spendable() {
+        let wallet = Wallet::generate();
+        let address = wallet.address();
+        let stake = wallet.stake(Amount::from_coins(10).unwrap(), 0);
+        assert_eq!(stake.debited_account(), Some(address.as_str()));
+        assert_eq!(stake.credited_account(), None);
+
+        let unstake = wallet.unstake(Amount::from_coins(10).unwrap(), 1);
+        assert_eq!(unstake.debited_account(), None);
+        assert_eq!(unstake.credited_account(), Some(address.as_str()));
+
+        // 交易类型受签名保护
+        let mut forged = unstake.clone();
+        forged.kind = TxKind::Transfer;
+        assert!(unstake.verify_signature().is_ok());
+        assert!(forged.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_unsigned_transaction_rejected() {
+        let wallet = Wallet::generate();
+        let transaction = Transaction::new(
+            wallet.address(),
+            "recipient".to_string(),
+            Amount::from_coins(10).unwrap(),
+        );
+        assert!(matches!(
+            transaction.verify_signature(),
+            Err(ChainError::MissingSignature)
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::amount::Amount;
+
+// 对某笔交易输出的引用 (txid:vout)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OutPoint {
+    pub txid: String,
+    pub vout: u32,
+}
+
+impl OutPoint {
+    pub fn new(txid: String, vout: u32) -> Self {
+        OutPoint { txid, vout }
+    }
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+// 交易输出: 付给 address 的金额
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxOutput {
+    pub address: String,
+    pub amount: Amount,
+}
+
+impl TxOutput {
+    pub fn new(address: String, amount: Amount) -> Self {
+        TxOutput { address, amount }
+    }
+}
+
+// 未花费交易输出集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UtxoSet {
+    outputs: HashMap<OutPoint, TxOutput>,
+}
+
+impl UtxoSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&TxOutput> {
+        self.outputs.get(outpoint)
+    }
+
+    pub fn contains(&self, outpoint: &OutPoint) -> bool {
+        self.outputs.contains_key(outpoint)
+    }
+
+    pub fn insert(&mut self, outpoint: OutPoint, output: TxOutput) {
+        self.outputs.insert(outpoint, output);
+    }
+
+    pub fn remove(&mut self, outpoint: &OutPoint) -> Option<TxOutput> {
+        self.outputs.remove(outpoint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    // address 名下的未花费输出, 按引用排序
+    pub fn unspent_for(&self, address: &str) -> Vec<(OutPoint, TxOutput)> {
+        let mut unspent: Vec<(OutPoint, TxOutput)> = self
+            .outputs
+            .iter()
+            .filter(|(_, output)| output.address == address)
+            .map(|(outpoint, output)| (outpoint.clone(), output.clone()))
+            .collect();
+        unspent.sort_by(|a, b| a.0.cmp(&b.0));
+        unspent
+    }
+}
@@ -0,0 +1,95 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+
+use crate::amount::Amount;
+use crate::error::ChainError;
+use crate::tx::Transaction;
+use crate::utxo::{OutPoint, TxOutput};
+
+// 钱包: 持有 ed25519 私钥, 地址即公钥的十六进制编码
+#[derive(Debug, Clone)]
+pub struct Wallet {
+    signing_key: SigningKey,
+}
+
+impl Wallet {
+    pub fn generate() -> Self {
+        Wallet {
+            signing_key: SigningKey::generate(&mut OsRng),
+        }
+    }
+
+    pub fn from_secret_bytes(secret: &[u8; 32]) -> Self {
+        Wallet {
+            signing_key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    pub fn address(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().as_bytes())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> String {
+        hex::encode(self.signing_key.sign(message).to_bytes())
+    }
+
+    // 创建一笔已签名的转账交易, nonce 为本钱包的下一个交易序号
+    pub fn transfer(&self, recipient: String, amount: Amount, nonce: u64) -> Transaction {
+        let mut transaction = Transaction::new(self.address(), recipient, amount).with_nonce(nonce);
+        transaction.sign(self);
+        transaction
+    }
+
+    // 创建一笔带手续费的已签名转账交易
+    pub fn transfer_with_fee(
+        &self,
+        recipient: String,
+        amount: Amount,
+        fee: Amount,
+        nonce: u64,
+    ) -> Transaction {
+        let mut transaction = Transaction::new(self.address(), recipient, amount)
+            .with_nonce(nonce)
+            .with_fee(fee);
+        transaction.sign(self);
+        transaction
+    }
+
+    // 创建一笔已签名的 UTXO 转账, inputs 必须都属于本钱包
+    pub fn spend(&self, inputs: Vec<OutPoint>, outputs: Vec<TxOutput>, nonce: u64) -> Transaction {
+        let mut transaction = Transaction::spend(self.address(), inputs, outputs).with_nonce(nonce);
+        transaction.sign(self);
+        transaction
+    }
+
+    // 创建一笔已签名的质押交易
+    pub fn stake(&self, amount: Amount, nonce: u64) -> Transaction {
+        let mut transaction = Transaction::stake(self.address(), amount).with_nonce(nonce);
+        transaction.sign(self);
+        transaction
+    }
+
+    pub fn unstake(&self, amount: Amount, nonce: u64) -> Transaction {
+        let mut transaction = Transaction::unstake(self.address(), amount).with_nonce(nonce);
+        transaction.sign(self);
+        transaction
+    }
+}
+
+// 用十六进制地址 (公钥) 校验十六进制签名
+pub fn verify_signature(address: &str, message: &[u8], signature: &str) -> Result<(), ChainError> {
+    let public_key: [u8; 32] = hex::decode(address)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ChainError::InvalidSignature)?;
+    let verifying_key =
+        VerifyingKey::from_bytes(&public_key).map_err(|_| ChainError::InvalidSignature)?;
+
+    let signature: [u8; 64] = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ChainError::InvalidSignature)?;
+    verifying_key
+        .verify_strict(message, &Signature::from_bytes(&signature))
+        .map_err(|_| ChainError::InvalidSignature)
+}